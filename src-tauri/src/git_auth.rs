@@ -0,0 +1,206 @@
+// Credentials for remote operations. libgit2 calls the credential callback again after every
+// rejected attempt, so each call hands out the next method in the chain:
+//   SSH:   ssh-agent, then key files (`maker.sshKey` entries in git config, then ~/.ssh/id_*)
+//   HTTPS: git credential helpers (`credential.helper`), then a token from the OS keyring
+// Tokens are stored per host under the `makercode-git` keyring service.
+
+use std::cell::RefCell;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use git2::{Config, Cred, CredentialType, Direction, RemoteCallbacks, Repository};
+use serde::Serialize;
+
+use super::git_sign::expand_home;
+
+const KEYRING_SERVICE: &str = "makercode-git";
+const DEFAULT_SSH_KEYS: [&str; 3] = ["id_ed25519", "id_ecdsa", "id_rsa"];
+
+// Labels of the credentials handed out so far, in order. After a successful connection
+// the last one is the method that worked.
+pub(crate) type AuthLog = Rc<RefCell<Vec<String>>>;
+
+#[derive(Serialize)]
+pub(crate) struct CredentialReport {
+    remote: String,
+    url: String,
+    ok: bool,
+    // None when the remote did not ask for credentials at all
+    method: Option<String>,
+    attempted: Vec<String>,
+    error: Option<String>,
+}
+
+// Host part of an https://, ssh:// or scp-style (user@host:path) URL
+fn url_host(url: &str) -> Option<String> {
+    let rest = match url.split_once("://") {
+        Some((_, rest)) => rest,
+        None => url.split_once(':')?.0,
+    };
+    let authority = rest.split('/').next()?;
+    let host = authority.rsplit('@').next()?;
+    let host = host.split(':').next()?;
+    (!host.is_empty()).then(|| host.to_string())
+}
+
+fn keyring_entry(host: &str) -> Result<keyring::Entry, String> {
+    keyring::Entry::new(KEYRING_SERVICE, host).map_err(|e| e.to_string())
+}
+
+fn ssh_key_files(config: &Config) -> Vec<PathBuf> {
+    let mut candidates: Vec<PathBuf> = Vec::new();
+    if let Ok(entries) = config.multivar("maker.sshkey", None) {
+        let _ = entries.for_each(|entry| {
+            if let Some(value) = entry.value() {
+                candidates.push(PathBuf::from(expand_home(value)));
+            }
+        });
+    }
+    if let Some(home) = std::env::var_os("HOME").or_else(|| std::env::var_os("USERPROFILE")) {
+        let ssh_dir = PathBuf::from(home).join(".ssh");
+        candidates.extend(DEFAULT_SSH_KEYS.iter().map(|name| ssh_dir.join(name)));
+    }
+    // A configured key may well be one of the defaults; offer each file once
+    let mut keys: Vec<PathBuf> = Vec::new();
+    for key in candidates {
+        if key.is_file() && !keys.contains(&key) {
+            keys.push(key);
+        }
+    }
+    keys
+}
+
+// Builds the credential callback. Every credential it hands out is recorded in `log`.
+pub(crate) fn credentials_callback(
+    config: Config,
+    log: AuthLog,
+) -> impl FnMut(&str, Option<&str>, CredentialType) -> Result<Cred, git2::Error> {
+    let mut agent_tried = false;
+    let mut key_files: Option<Vec<PathBuf>> = None;
+    let mut helper_tried = false;
+    let mut keyring_tried = false;
+    let mut default_tried = false;
+
+    move |url, username, allowed| {
+        let record = |label: String| log.borrow_mut().push(label);
+
+        // SSH URLs without a user first ask for the user name alone
+        if allowed.contains(CredentialType::USERNAME) {
+            return Cred::username(username.unwrap_or("git"));
+        }
+
+        if allowed.contains(CredentialType::SSH_KEY) {
+            let user = username.unwrap_or("git");
+            if !agent_tried {
+                agent_tried = true;
+                record("ssh-agent".to_string());
+                if let Ok(cred) = Cred::ssh_key_from_agent(user) {
+                    return Ok(cred);
+                }
+            }
+            let keys = key_files.get_or_insert_with(|| {
+                let mut keys = ssh_key_files(&config);
+                keys.reverse();
+                keys
+            });
+            while let Some(key) = keys.pop() {
+                record(format!("ssh-key:{}", key.display()));
+                if let Ok(cred) = Cred::ssh_key(user, None, &key, None) {
+                    return Ok(cred);
+                }
+            }
+        }
+
+        if allowed.contains(CredentialType::USER_PASS_PLAINTEXT) {
+            if !helper_tried {
+                helper_tried = true;
+                if let Ok(cred) = Cred::credential_helper(&config, url, username) {
+                    record("credential-helper".to_string());
+                    return Ok(cred);
+                }
+            }
+            if !keyring_tried {
+                keyring_tried = true;
+                let token = url_host(url).and_then(|host| keyring_entry(&host).ok()?.get_password().ok());
+                if let Some(token) = token {
+                    record("keyring".to_string());
+                    // Token-based hosts accept any user name alongside the token
+                    return Cred::userpass_plaintext(username.unwrap_or("x-access-token"), &token);
+                }
+            }
+        }
+
+        // NTLM/Negotiate on Windows and Kerberos hosts
+        if allowed.contains(CredentialType::DEFAULT) && !default_tried {
+            default_tried = true;
+            record("default".to_string());
+            return Cred::default();
+        }
+
+        Err(git2::Error::from_str(&format!(
+            "No more credentials to try for {} (attempted: {})",
+            url,
+            log.borrow().join(", ")
+        )))
+    }
+}
+
+// Connects to `remote` (default origin) the way a fetch would, or a push with `push`,
+// and reports which credential method got through.
+#[tauri::command]
+pub async fn git_test_credentials(
+    path: String,
+    remote: Option<String>,
+    push: Option<bool>,
+) -> Result<CredentialReport, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let repo = Repository::open(&path).map_err(|e| e.message().to_string())?;
+        let remote_name = remote.unwrap_or_else(|| "origin".to_string());
+        let mut remote = repo
+            .find_remote(&remote_name)
+            .map_err(|e| format!("Unknown remote '{}': {}", remote_name, e.message()))?;
+        let direction = if push.unwrap_or(false) { Direction::Push } else { Direction::Fetch };
+        let url = match direction {
+            Direction::Push => remote.pushurl().or(remote.url()),
+            Direction::Fetch => remote.url(),
+        }
+        .unwrap_or("")
+        .to_string();
+
+        let log = AuthLog::default();
+        let config = repo.config().map_err(|e| e.message().to_string())?;
+        let mut callbacks = RemoteCallbacks::new();
+        callbacks.credentials(credentials_callback(config, log.clone()));
+        let result = remote
+            .connect_auth(direction, Some(callbacks), None)
+            .map(drop)
+            .map_err(|e| e.message().to_string());
+
+        let attempted = log.borrow().clone();
+        Ok(CredentialReport {
+            remote: remote_name,
+            url,
+            ok: result.is_ok(),
+            method: result.as_ref().ok().and_then(|_| attempted.last().cloned()),
+            attempted,
+            error: result.err(),
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+// Stores an access token for `host` (e.g. "github.com") in the OS keyring
+#[tauri::command]
+pub fn git_store_token(host: String, token: String) -> Result<(), String> {
+    keyring_entry(&host)?.set_password(&token).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn git_delete_token(host: String) -> Result<bool, String> {
+    match keyring_entry(&host)?.delete_credential() {
+        Ok(()) => Ok(true),
+        Err(keyring::Error::NoEntry) => Ok(false),
+        Err(e) => Err(e.to_string()),
+    }
+}
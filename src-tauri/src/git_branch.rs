@@ -0,0 +1,291 @@
+// Listing, creating, switching, deleting and renaming branches.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use git2::build::CheckoutBuilder;
+use git2::{Branch, BranchType, Commit, Repository, StatusOptions};
+use serde::Serialize;
+
+use super::git_worktree::open_main_repo;
+use super::{get_git_status, resolve_commit, GitStatus};
+
+#[derive(Serialize)]
+pub(crate) struct BranchCommit {
+    oid: String,
+    short_oid: String,
+    summary: String,
+    author: String,
+    // Seconds since the Unix epoch
+    time: i64,
+}
+
+#[derive(Serialize)]
+pub(crate) struct BranchInfo {
+    // Short name, e.g. "main" or "origin/main"
+    name: String,
+    is_remote: bool,
+    is_head: bool,
+    // Short name of the tracked branch, for local branches
+    upstream: Option<String>,
+    ahead: usize,
+    behind: usize,
+    last_commit: Option<BranchCommit>,
+}
+
+fn branch_commit(commit: &Commit) -> BranchCommit {
+    let oid = commit.id().to_string();
+    BranchCommit {
+        short_oid: oid[..7].to_string(),
+        oid,
+        summary: commit.summary().unwrap_or("").to_string(),
+        author: String::from_utf8_lossy(commit.author().name_bytes()).to_string(),
+        time: commit.time().seconds(),
+    }
+}
+
+fn branch_info(repo: &Repository, branch: &Branch, kind: BranchType) -> Result<BranchInfo, String> {
+    let name = branch
+        .name()
+        .map_err(|e| e.message().to_string())?
+        .ok_or_else(|| "Branch name is not valid UTF-8".to_string())?
+        .to_string();
+    let tip = branch.get().peel_to_commit().ok();
+
+    let mut info = BranchInfo {
+        name,
+        is_remote: kind == BranchType::Remote,
+        is_head: branch.is_head(),
+        upstream: None,
+        ahead: 0,
+        behind: 0,
+        last_commit: tip.as_ref().map(branch_commit),
+    };
+    if let Ok(upstream) = branch.upstream() {
+        info.upstream = upstream.name().ok().flatten().map(str::to_string);
+        if let (Some(local), Some(remote)) = (tip.as_ref().map(Commit::id), upstream.get().target()) {
+            if let Ok((ahead, behind)) = repo.graph_ahead_behind(local, remote) {
+                info.ahead = ahead;
+                info.behind = behind;
+            }
+        }
+    }
+    Ok(info)
+}
+
+fn find_local<'r>(repo: &'r Repository, name: &str) -> Result<Branch<'r>, String> {
+    repo.find_branch(name, BranchType::Local)
+        .map_err(|_| format!("Branch '{}' does not exist", name))
+}
+
+fn validate_name(name: &str) -> Result<(), String> {
+    if Branch::name_is_valid(name).map_err(|e| e.message().to_string())? {
+        Ok(())
+    } else {
+        Err(format!("'{}' is not a valid branch name", name))
+    }
+}
+
+// Paths with staged or unstaged changes to tracked files. Untracked files don't block a
+// checkout; a safe checkout still refuses to overwrite them.
+fn tracked_changes(repo: &Repository) -> Result<Vec<String>, String> {
+    let mut opts = StatusOptions::new();
+    opts.include_untracked(false).include_ignored(false);
+    let statuses = repo.statuses(Some(&mut opts)).map_err(|e| e.message().to_string())?;
+    Ok(statuses
+        .iter()
+        .filter_map(|entry| entry.path().map(str::to_string))
+        .collect())
+}
+
+// Another working tree (a linked worktree, or the main one when `repo` is itself a linked
+// worktree) that has `refname` checked out. libgit2 refuses to point HEAD at such a branch.
+fn checked_out_elsewhere(repo: &Repository, refname: &str) -> Result<Option<PathBuf>, String> {
+    let canonical = |p: &Path| fs::canonicalize(p).unwrap_or_else(|_| p.to_path_buf());
+    let own = repo.workdir().map(canonical);
+    let main = open_main_repo(&repo.path().to_string_lossy())?;
+
+    let mut dirs: Vec<PathBuf> = main.workdir().map(Path::to_path_buf).into_iter().collect();
+    let names = main.worktrees().map_err(|e| e.message().to_string())?;
+    for name in names.iter().flatten() {
+        if let Ok(worktree) = main.find_worktree(name) {
+            if worktree.validate().is_ok() {
+                dirs.push(worktree.path().to_path_buf());
+            }
+        }
+    }
+
+    for dir in dirs {
+        if Some(canonical(&dir)) == own {
+            continue;
+        }
+        let head = Repository::open(&dir)
+            .ok()
+            .and_then(|other| other.find_reference("HEAD").ok()?.symbolic_target().map(str::to_string));
+        if head.as_deref() == Some(refname) {
+            return Ok(Some(dir));
+        }
+    }
+    Ok(None)
+}
+
+// Local branches first, then remote-tracking branches, each sorted by name.
+// The remotes' symbolic HEAD refs (origin/HEAD) are left out.
+#[tauri::command]
+pub fn git_branches(path: String) -> Result<Vec<BranchInfo>, String> {
+    let repo = Repository::open(&path).map_err(|e| e.message().to_string())?;
+    let mut branches = Vec::new();
+    for kind in [BranchType::Local, BranchType::Remote] {
+        let mut group = Vec::new();
+        for entry in repo.branches(Some(kind)).map_err(|e| e.message().to_string())? {
+            let (branch, kind) = entry.map_err(|e| e.message().to_string())?;
+            if branch.get().symbolic_target().is_some() {
+                continue;
+            }
+            group.push(branch_info(&repo, &branch, kind)?);
+        }
+        group.sort_by(|a, b| a.name.cmp(&b.name));
+        branches.extend(group);
+    }
+    Ok(branches)
+}
+
+// Creates `name` at `start` (any revspec, default HEAD) without switching to it
+#[tauri::command]
+pub fn git_create_branch(path: String, name: String, start: Option<String>) -> Result<BranchInfo, String> {
+    let repo = Repository::open(&path).map_err(|e| e.message().to_string())?;
+    validate_name(&name)?;
+    let spec = start.as_deref().unwrap_or("HEAD");
+    let target = resolve_commit(&repo, spec)?;
+    let branch = repo.branch(&name, &target, false).map_err(|e| e.message().to_string())?;
+    branch_info(&repo, &branch, BranchType::Local)
+}
+
+// Switches to the local branch `branch`. A remote-tracking name such as "origin/feature"
+// creates (or reuses) the local "feature" branch tracking it, like `git checkout feature`
+// does. Refuses when tracked files have uncommitted changes unless `force`, which discards them.
+#[tauri::command]
+pub fn git_checkout(path: String, branch: String, force: Option<bool>) -> Result<GitStatus, String> {
+    let repo = Repository::open(&path).map_err(|e| e.message().to_string())?;
+    let force = force.unwrap_or(false);
+    let current = repo.head().ok().and_then(|head| head.shorthand().map(str::to_string));
+    if current.as_deref() == Some(branch.as_str()) {
+        return get_git_status(path, None);
+    }
+
+    if !force {
+        let changed = tracked_changes(&repo)?;
+        if !changed.is_empty() {
+            return Err(format!(
+                "Cannot check out '{}': uncommitted changes in {} ({} file(s)); commit or stash them, \
+                 or force the checkout to discard them",
+                branch,
+                changed.join(", "),
+                changed.len()
+            ));
+        }
+    }
+
+    let local = match repo.find_branch(&branch, BranchType::Local) {
+        Ok(local) => local,
+        Err(_) => {
+            let remote = repo
+                .find_branch(&branch, BranchType::Remote)
+                .map_err(|_| format!("Branch '{}' does not exist", branch))?;
+            let remote_name = repo
+                .branch_remote_name(remote.get().name().unwrap_or(""))
+                .map_err(|e| e.message().to_string())?;
+            let prefix = format!("{}/", remote_name.as_str().unwrap_or(""));
+            let local_name = branch.strip_prefix(&prefix).unwrap_or(&branch);
+            match repo.find_branch(local_name, BranchType::Local) {
+                Ok(existing) => existing,
+                Err(_) => {
+                    let tip = remote.get().peel_to_commit().map_err(|e| e.message().to_string())?;
+                    let mut created = repo
+                        .branch(local_name, &tip, false)
+                        .map_err(|e| e.message().to_string())?;
+                    created.set_upstream(Some(&branch)).map_err(|e| e.message().to_string())?;
+                    created
+                }
+            }
+        }
+    };
+    if local.is_head() {
+        return get_git_status(path, None);
+    }
+
+    let reference = local.into_reference();
+    let refname = reference
+        .name()
+        .ok_or_else(|| "Branch name is not valid UTF-8".to_string())?
+        .to_string();
+    if let Some(dir) = checked_out_elsewhere(&repo, &refname)? {
+        return Err(format!("Cannot check out '{}': it is checked out in {}", branch, dir.display()));
+    }
+
+    let tree = reference.peel_to_tree().map_err(|e| e.message().to_string())?;
+    let mut checkout = CheckoutBuilder::new();
+    if force {
+        checkout.force();
+    } else {
+        checkout.safe();
+    }
+    repo.checkout_tree(tree.as_object(), Some(&mut checkout))
+        .map_err(|e| format!("Cannot update the working tree: {}", e.message()))?;
+    if let Err(e) = repo.set_head(&refname) {
+        // Put back the files of the branch HEAD still points at. Local changes were either
+        // absent (checked above) or meant to be discarded (`force`).
+        if let Err(restore) = repo.checkout_head(Some(CheckoutBuilder::new().force())) {
+            log::warn!("Failed to restore the working tree after a failed checkout: {}", restore.message());
+        }
+        return Err(e.message().to_string());
+    }
+    get_git_status(path, None)
+}
+
+// Deletes a local branch. Unless `force`, the branch has to be merged into HEAD or its
+// upstream, so no commits are lost.
+#[tauri::command]
+pub fn git_delete_branch(path: String, name: String, force: Option<bool>) -> Result<(), String> {
+    let repo = Repository::open(&path).map_err(|e| e.message().to_string())?;
+    let mut branch = find_local(&repo, &name)?;
+    if branch.is_head() {
+        return Err(format!("Cannot delete '{}': it is the current branch", name));
+    }
+
+    if !force.unwrap_or(false) {
+        let tip = branch.get().target().ok_or_else(|| format!("Branch '{}' has no commit", name))?;
+        let mut bases: Vec<git2::Oid> = Vec::new();
+        bases.extend(repo.head().ok().and_then(|head| head.target()));
+        bases.extend(branch.upstream().ok().and_then(|up| up.get().target()));
+        let merged = bases
+            .iter()
+            .any(|&base| base == tip || repo.graph_descendant_of(base, tip).unwrap_or(false));
+        if !merged {
+            return Err(format!(
+                "Branch '{}' is not fully merged; force the deletion to discard its commits",
+                name
+            ));
+        }
+    }
+
+    branch.delete().map_err(|e| e.message().to_string())
+}
+
+// Renames a local branch, carrying its upstream configuration and HEAD along. With `force`
+// an existing branch called `new_name` is overwritten.
+#[tauri::command]
+pub fn git_rename_branch(
+    path: String,
+    old_name: String,
+    new_name: String,
+    force: Option<bool>,
+) -> Result<BranchInfo, String> {
+    let repo = Repository::open(&path).map_err(|e| e.message().to_string())?;
+    validate_name(&new_name)?;
+    let mut branch = find_local(&repo, &old_name)?;
+    let renamed = branch
+        .rename(&new_name, force.unwrap_or(false))
+        .map_err(|e| e.message().to_string())?;
+    branch_info(&repo, &renamed, BranchType::Local)
+}
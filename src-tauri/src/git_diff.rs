@@ -0,0 +1,253 @@
+// Structured diffs between any two of: a commit/tree, the index and the working directory.
+
+use git2::{Delta, Diff, DiffFindOptions, DiffFormat, DiffOptions, Patch, Repository, Tree};
+use serde::{Deserialize, Serialize};
+
+// Pseudo-revisions accepted by `git_diff` besides regular revspecs
+const INDEX: &str = "INDEX";
+const WORKDIR: &str = "WORKDIR";
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+pub(crate) struct DiffRequestOptions {
+    // Lines of context around each hunk (git's default is 3)
+    context_lines: Option<u32>,
+    // Defaults to true
+    detect_renames: Option<bool>,
+    // Untracked files show up as added when diffing against the working directory. Defaults to true.
+    include_untracked: Option<bool>,
+    ignore_whitespace: bool,
+    // Only diff these paths (pathspecs, relative to the repository root)
+    paths: Vec<String>,
+    // Also render the whole diff as unified patch text
+    patch: bool,
+}
+
+#[derive(Serialize)]
+pub(crate) struct DiffLineInfo {
+    // '+', '-' or ' ', or one of '=', '>', '<' for the "no newline at end of file" markers
+    origin: char,
+    content: String,
+    old_lineno: Option<u32>,
+    new_lineno: Option<u32>,
+}
+
+#[derive(Serialize)]
+pub(crate) struct DiffHunkInfo {
+    header: String,
+    old_start: u32,
+    old_lines: u32,
+    new_start: u32,
+    new_lines: u32,
+    lines: Vec<DiffLineInfo>,
+}
+
+#[derive(Serialize)]
+pub(crate) struct DiffFileInfo {
+    old_path: Option<String>,
+    new_path: Option<String>,
+    // "added", "deleted", "modified", "renamed", "copied", "typechange", "untracked" or "conflicted"
+    status: &'static str,
+    is_binary: bool,
+    insertions: usize,
+    deletions: usize,
+    // Empty for binary files
+    hunks: Vec<DiffHunkInfo>,
+}
+
+#[derive(Serialize)]
+pub(crate) struct GitDiff {
+    files: Vec<DiffFileInfo>,
+    files_changed: usize,
+    insertions: usize,
+    deletions: usize,
+    patch: Option<String>,
+}
+
+enum DiffSide<'r> {
+    Tree(Tree<'r>),
+    Index,
+    Workdir,
+}
+
+fn parse_side<'r>(repo: &'r Repository, spec: &str) -> Result<DiffSide<'r>, String> {
+    match spec {
+        INDEX => Ok(DiffSide::Index),
+        WORKDIR => Ok(DiffSide::Workdir),
+        _ => repo
+            .revparse_single(spec)
+            .and_then(|obj| obj.peel_to_tree())
+            .map(DiffSide::Tree)
+            .map_err(|e| format!("Cannot resolve '{}': {}", spec, e.message())),
+    }
+}
+
+fn delta_status(status: Delta) -> &'static str {
+    match status {
+        Delta::Added => "added",
+        Delta::Deleted => "deleted",
+        Delta::Renamed => "renamed",
+        Delta::Copied => "copied",
+        Delta::Typechange => "typechange",
+        Delta::Untracked => "untracked",
+        Delta::Conflicted => "conflicted",
+        _ => "modified",
+    }
+}
+
+// libgit2 only diffs "older" sides against "newer" ones (tree < index < workdir);
+// the other directions are the same diff computed in reverse.
+fn build_diff<'r>(
+    repo: &'r Repository,
+    from: &DiffSide<'r>,
+    to: &DiffSide<'r>,
+    opts: &mut DiffOptions,
+) -> Result<Diff<'r>, String> {
+    let rank = |side: &DiffSide| match side {
+        DiffSide::Tree(_) => 0,
+        DiffSide::Index => 1,
+        DiffSide::Workdir => 2,
+    };
+    let (old, new) = if rank(from) > rank(to) {
+        opts.reverse(true);
+        (to, from)
+    } else {
+        (from, to)
+    };
+
+    let diff = match (old, new) {
+        (DiffSide::Tree(a), DiffSide::Tree(b)) => repo.diff_tree_to_tree(Some(a), Some(b), Some(opts)),
+        (DiffSide::Tree(a), DiffSide::Index) => repo.diff_tree_to_index(Some(a), None, Some(opts)),
+        (DiffSide::Tree(a), DiffSide::Workdir) => repo.diff_tree_to_workdir_with_index(Some(a), Some(opts)),
+        (DiffSide::Index, DiffSide::Workdir) => repo.diff_index_to_workdir(None, Some(opts)),
+        (DiffSide::Index, DiffSide::Index) | (DiffSide::Workdir, DiffSide::Workdir) => {
+            return Err("Cannot diff a side against itself".to_string())
+        }
+        _ => unreachable!("sides are ordered above"),
+    };
+    diff.map_err(|e| e.message().to_string())
+}
+
+fn file_info(patch: &Patch) -> Result<DiffFileInfo, String> {
+    let delta = patch.delta();
+    let path_of = |file: git2::DiffFile| file.path().map(|p| p.to_string_lossy().to_string());
+    let is_binary = delta.flags().is_binary();
+
+    let mut hunks = Vec::new();
+    if !is_binary {
+        for hunk_idx in 0..patch.num_hunks() {
+            let (hunk, line_count) = patch.hunk(hunk_idx).map_err(|e| e.message().to_string())?;
+            let mut lines = Vec::with_capacity(line_count);
+            for line_idx in 0..line_count {
+                let line = patch
+                    .line_in_hunk(hunk_idx, line_idx)
+                    .map_err(|e| e.message().to_string())?;
+                lines.push(DiffLineInfo {
+                    origin: line.origin(),
+                    content: String::from_utf8_lossy(line.content()).to_string(),
+                    old_lineno: line.old_lineno(),
+                    new_lineno: line.new_lineno(),
+                });
+            }
+            hunks.push(DiffHunkInfo {
+                header: String::from_utf8_lossy(hunk.header()).trim_end().to_string(),
+                old_start: hunk.old_start(),
+                old_lines: hunk.old_lines(),
+                new_start: hunk.new_start(),
+                new_lines: hunk.new_lines(),
+                lines,
+            });
+        }
+    }
+
+    let (_, insertions, deletions) = patch.line_stats().map_err(|e| e.message().to_string())?;
+    Ok(DiffFileInfo {
+        old_path: path_of(delta.old_file()),
+        new_path: path_of(delta.new_file()),
+        status: delta_status(delta.status()),
+        is_binary,
+        insertions,
+        deletions,
+        hunks,
+    })
+}
+
+fn patch_text(diff: &Diff) -> Result<String, String> {
+    let mut text = String::new();
+    diff.print(DiffFormat::Patch, |_, _, line| {
+        // Header lines already carry their own text; content lines need their origin prefix
+        if matches!(line.origin(), '+' | '-' | ' ') {
+            text.push(line.origin());
+        }
+        text.push_str(&String::from_utf8_lossy(line.content()));
+        true
+    })
+    .map_err(|e| e.message().to_string())?;
+    Ok(text)
+}
+
+fn diff_blocking(
+    path: String,
+    from: Option<String>,
+    to: Option<String>,
+    options: Option<DiffRequestOptions>,
+) -> Result<GitDiff, String> {
+    let repo = Repository::open(&path).map_err(|e| e.message().to_string())?;
+    let options = options.unwrap_or_default();
+    let from = parse_side(&repo, from.as_deref().unwrap_or(INDEX))?;
+    let to = parse_side(&repo, to.as_deref().unwrap_or(WORKDIR))?;
+
+    let include_untracked = options.include_untracked.unwrap_or(true);
+    let mut opts = DiffOptions::new();
+    opts.include_untracked(include_untracked)
+        .recurse_untracked_dirs(include_untracked)
+        .show_untracked_content(include_untracked)
+        .include_typechange(true)
+        .ignore_whitespace(options.ignore_whitespace);
+    if let Some(context) = options.context_lines {
+        opts.context_lines(context);
+    }
+    for pathspec in &options.paths {
+        opts.pathspec(pathspec);
+    }
+
+    let mut diff = build_diff(&repo, &from, &to, &mut opts)?;
+    if options.detect_renames.unwrap_or(true) {
+        let mut find = DiffFindOptions::new();
+        find.renames(true).for_untracked(include_untracked);
+        diff.find_similar(Some(&mut find))
+            .map_err(|e| e.message().to_string())?;
+    }
+
+    let mut files = Vec::new();
+    for idx in 0..diff.deltas().len() {
+        if let Some(patch) = Patch::from_diff(&diff, idx).map_err(|e| e.message().to_string())? {
+            files.push(file_info(&patch)?);
+        }
+    }
+
+    let stats = diff.stats().map_err(|e| e.message().to_string())?;
+    let patch = if options.patch { Some(patch_text(&diff)?) } else { None };
+    Ok(GitDiff {
+        files,
+        files_changed: stats.files_changed(),
+        insertions: stats.insertions(),
+        deletions: stats.deletions(),
+        patch,
+    })
+}
+
+// Diffs `from` against `to`. Each side is a revspec (commit or tree), "INDEX" or "WORKDIR".
+// By default `from` is INDEX and `to` is WORKDIR, i.e. the unstaged changes; with default
+// options the hunks are in the order `git_stage_hunks` numbers them.
+#[tauri::command]
+pub async fn git_diff(
+    path: String,
+    from: Option<String>,
+    to: Option<String>,
+    options: Option<DiffRequestOptions>,
+) -> Result<GitDiff, String> {
+    tauri::async_runtime::spawn_blocking(move || diff_blocking(path, from, to, options))
+        .await
+        .map_err(|e| e.to_string())?
+}
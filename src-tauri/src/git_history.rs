@@ -0,0 +1,189 @@
+// Paginated commit history with per-commit diff stats, replacing the `git log` text parsing.
+
+use git2::{Commit, Repository, Signature, Sort};
+use serde::Serialize;
+
+use super::resolve_commit;
+
+const DEFAULT_PAGE_SIZE: usize = 20;
+
+#[derive(Serialize)]
+pub(crate) struct GitPerson {
+    name: String,
+    email: String,
+    // Seconds since the Unix epoch
+    time: i64,
+    // Timezone offset from UTC in minutes
+    offset_minutes: i32,
+}
+
+#[derive(Serialize)]
+pub(crate) struct CommitStats {
+    files_changed: usize,
+    insertions: usize,
+    deletions: usize,
+}
+
+#[derive(Serialize)]
+pub(crate) struct GitLogEntry {
+    oid: String,
+    short_oid: String,
+    summary: String,
+    // Everything after the summary line, if anything
+    body: Option<String>,
+    author: GitPerson,
+    committer: GitPerson,
+    parents: Vec<String>,
+    // Against the first parent (or the empty tree for a root commit)
+    stats: CommitStats,
+}
+
+#[derive(Serialize)]
+pub(crate) struct GitLogPage {
+    entries: Vec<GitLogEntry>,
+    offset: usize,
+    has_more: bool,
+}
+
+fn person(signature: &Signature) -> GitPerson {
+    GitPerson {
+        name: String::from_utf8_lossy(signature.name_bytes()).to_string(),
+        email: String::from_utf8_lossy(signature.email_bytes()).to_string(),
+        time: signature.when().seconds(),
+        offset_minutes: signature.when().offset_minutes(),
+    }
+}
+
+fn touches_path(path: &str, filter: &str) -> bool {
+    path == filter || path.strip_prefix(filter).is_some_and(|rest| rest.starts_with('/'))
+}
+
+// Stats for a commit, or None when `path_filter` is set and the commit does not touch it
+fn commit_stats(repo: &Repository, commit: &Commit, path_filter: Option<&str>) -> Result<Option<CommitStats>, String> {
+    let tree = commit.tree().map_err(|e| e.message().to_string())?;
+    let parent_tree = match commit.parent(0) {
+        Ok(parent) => Some(parent.tree().map_err(|e| e.message().to_string())?),
+        Err(_) => None,
+    };
+    let diff = repo
+        .diff_tree_to_tree(parent_tree.as_ref(), Some(&tree), None)
+        .map_err(|e| e.message().to_string())?;
+
+    if let Some(filter) = path_filter {
+        let touched = diff.deltas().any(|delta| {
+            [delta.old_file().path(), delta.new_file().path()]
+                .into_iter()
+                .flatten()
+                .any(|p| touches_path(&p.to_string_lossy(), filter))
+        });
+        if !touched {
+            return Ok(None);
+        }
+    }
+
+    let stats = diff.stats().map_err(|e| e.message().to_string())?;
+    Ok(Some(CommitStats {
+        files_changed: stats.files_changed(),
+        insertions: stats.insertions(),
+        deletions: stats.deletions(),
+    }))
+}
+
+fn log_entry(commit: &Commit, stats: CommitStats) -> GitLogEntry {
+    let oid = commit.id().to_string();
+    let body = commit
+        .body()
+        .map(|b| b.trim().to_string())
+        .filter(|b| !b.is_empty());
+    GitLogEntry {
+        short_oid: oid[..7].to_string(),
+        oid,
+        summary: commit.summary().unwrap_or("").to_string(),
+        body,
+        author: person(&commit.author()),
+        committer: person(&commit.committer()),
+        parents: commit.parent_ids().map(|id| id.to_string()).collect(),
+        stats,
+    }
+}
+
+fn log_blocking(
+    path: String,
+    reference: Option<String>,
+    offset: Option<usize>,
+    limit: Option<usize>,
+    path_filter: Option<String>,
+) -> Result<GitLogPage, String> {
+    let repo = Repository::open(&path).map_err(|e| e.message().to_string())?;
+    let offset = offset.unwrap_or(0);
+    let limit = limit.unwrap_or(DEFAULT_PAGE_SIZE);
+    let path_filter = path_filter
+        .as_deref()
+        .map(|p| p.trim_matches('/'))
+        .filter(|p| !p.is_empty());
+
+    let start = match &reference {
+        Some(spec) => resolve_commit(&repo, spec)?.id(),
+        None => match repo.head().ok().and_then(|head| head.target()) {
+            Some(oid) => oid,
+            // Unborn branch: no history yet
+            None => {
+                return Ok(GitLogPage {
+                    entries: Vec::new(),
+                    offset,
+                    has_more: false,
+                })
+            }
+        },
+    };
+
+    let mut walk = repo.revwalk().map_err(|e| e.message().to_string())?;
+    walk.set_sorting(Sort::TOPOLOGICAL | Sort::TIME).map_err(|e| e.message().to_string())?;
+    walk.push(start).map_err(|e| e.message().to_string())?;
+
+    let mut entries = Vec::new();
+    let mut skipped = 0;
+    let mut has_more = false;
+    for oid in walk {
+        let oid = oid.map_err(|e| e.message().to_string())?;
+        let commit = repo.find_commit(oid).map_err(|e| e.message().to_string())?;
+
+        // Without a filter the skipped commits need no diff at all
+        if path_filter.is_none() && skipped < offset {
+            skipped += 1;
+            continue;
+        }
+        let Some(stats) = commit_stats(&repo, &commit, path_filter)? else { continue };
+        if skipped < offset {
+            skipped += 1;
+            continue;
+        }
+        if entries.len() == limit {
+            has_more = true;
+            break;
+        }
+        entries.push(log_entry(&commit, stats));
+    }
+
+    Ok(GitLogPage {
+        entries,
+        offset,
+        has_more,
+    })
+}
+
+// Walks history from `reference` (any revspec, default HEAD), newest first. With
+// `path_filter` only commits touching that file or directory are listed, and `offset`
+// counts matching commits.
+#[tauri::command]
+pub async fn git_log(
+    path: String,
+    reference: Option<String>,
+    offset: Option<usize>,
+    limit: Option<usize>,
+    path_filter: Option<String>,
+) -> Result<GitLogPage, String> {
+    tauri::async_runtime::spawn_blocking(move || log_blocking(path, reference, offset, limit, path_filter))
+        .await
+        .map_err(|e| e.to_string())?
+}
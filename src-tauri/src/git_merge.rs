@@ -0,0 +1,532 @@
+// In-process squash merge of a step branch into the current branch. Clean merges are
+// committed directly; conflicting ones are left in the index and working tree (like
+// `git merge --squash`) and reported with the ancestor / ours / theirs contents.
+// `git_merge_preview` predicts the outcome of a merge without touching either.
+
+use git2::build::CheckoutBuilder;
+use git2::{
+    BranchType, Commit, DiffOptions, Index, IndexEntry, MergeOptions, Oid, Patch, Repository,
+    StatusOptions,
+};
+use serde::Serialize;
+
+use super::git_sign::write_commit;
+use super::{commit_identities, resolve_commit, CommitIdentity};
+
+#[derive(Serialize)]
+pub(crate) struct MergeConflictEntry {
+    path: String,
+    // None when the file does not exist on that side
+    ancestor: Option<String>,
+    ours: Option<String>,
+    theirs: Option<String>,
+    // Contents of binary sides are left out
+    is_binary: bool,
+}
+
+#[derive(Serialize)]
+pub(crate) struct SquashMergeResult {
+    // False when the merge stopped on conflicts
+    merged: bool,
+    // The squash commit; None when there was nothing to merge or on conflicts
+    commit: Option<String>,
+    conflicts: Vec<MergeConflictEntry>,
+}
+
+fn find_branch_commit<'r>(repo: &'r Repository, branch: &str) -> Result<Commit<'r>, String> {
+    let reference = match repo.find_branch(branch, BranchType::Local) {
+        Ok(local) => local.into_reference(),
+        Err(_) => repo
+            .resolve_reference_from_short_name(branch)
+            .map_err(|e| format!("Unknown branch '{}': {}", branch, e.message()))?,
+    };
+    reference.peel_to_commit().map_err(|e| e.message().to_string())
+}
+
+fn has_tracked_changes(repo: &Repository) -> Result<bool, String> {
+    let mut opts = StatusOptions::new();
+    opts.include_untracked(false).include_ignored(false);
+    let statuses = repo.statuses(Some(&mut opts)).map_err(|e| e.message().to_string())?;
+    Ok(!statuses.is_empty())
+}
+
+fn blob_side(repo: &Repository, entry: Option<&IndexEntry>) -> Result<(Option<String>, bool), String> {
+    let Some(entry) = entry else { return Ok((None, false)) };
+    let blob = repo.find_blob(entry.id).map_err(|e| e.message().to_string())?;
+    if blob.is_binary() {
+        return Ok((None, true));
+    }
+    Ok((Some(String::from_utf8_lossy(blob.content()).to_string()), false))
+}
+
+fn collect_conflicts(repo: &Repository, index: &Index) -> Result<Vec<MergeConflictEntry>, String> {
+    let mut result = Vec::new();
+    for conflict in index.conflicts().map_err(|e| e.message().to_string())? {
+        let conflict = conflict.map_err(|e| e.message().to_string())?;
+        let path = [&conflict.our, &conflict.their, &conflict.ancestor]
+            .into_iter()
+            .flatten()
+            .next()
+            .map(|entry| String::from_utf8_lossy(&entry.path).to_string())
+            .unwrap_or_default();
+
+        let (ancestor, ancestor_binary) = blob_side(repo, conflict.ancestor.as_ref())?;
+        let (ours, ours_binary) = blob_side(repo, conflict.our.as_ref())?;
+        let (theirs, theirs_binary) = blob_side(repo, conflict.their.as_ref())?;
+        result.push(MergeConflictEntry {
+            path,
+            ancestor,
+            ours,
+            theirs,
+            is_binary: ancestor_binary || ours_binary || theirs_binary,
+        });
+    }
+    Ok(result)
+}
+
+// Runs libgit2's merge so the conflicts land in the index and working tree with markers,
+// then drops MERGE_HEAD so the eventual commit has a single parent, as with `--squash`.
+fn write_conflicted_state(repo: &Repository, theirs: Oid) -> Result<(), String> {
+    let annotated = repo.find_annotated_commit(theirs).map_err(|e| e.message().to_string())?;
+    let mut checkout = CheckoutBuilder::new();
+    checkout.allow_conflicts(true).conflict_style_merge(true);
+    repo.merge(&[&annotated], Some(&mut MergeOptions::new()), Some(&mut checkout))
+        .map_err(|e| e.message().to_string())?;
+    repo.cleanup_state().map_err(|e| e.message().to_string())
+}
+
+// Squash-merges `branch` into HEAD. Requires a working tree without tracked changes.
+// `author`, `committer`, `co_author_agent` and `sign` work as for `git_commit`.
+#[tauri::command]
+pub fn git_squash_merge(
+    path: String,
+    branch: String,
+    message: String,
+    author: Option<CommitIdentity>,
+    committer: Option<CommitIdentity>,
+    co_author_agent: Option<bool>,
+    sign: Option<bool>,
+) -> Result<SquashMergeResult, String> {
+    let repo = Repository::open(&path).map_err(|e| e.message().to_string())?;
+    let head = repo
+        .head()
+        .and_then(|head| head.peel_to_commit())
+        .map_err(|_| "HEAD is not pointing to a commit".to_string())?;
+    let theirs = find_branch_commit(&repo, &branch)?;
+
+    let up_to_date = SquashMergeResult {
+        merged: true,
+        commit: None,
+        conflicts: Vec::new(),
+    };
+    if theirs.id() == head.id()
+        || repo
+            .graph_descendant_of(head.id(), theirs.id())
+            .map_err(|e| e.message().to_string())?
+    {
+        return Ok(up_to_date);
+    }
+    if has_tracked_changes(&repo)? {
+        return Err("Working tree has uncommitted changes".to_string());
+    }
+
+    let mut merged = repo
+        .merge_commits(&head, &theirs, None)
+        .map_err(|e| e.message().to_string())?;
+
+    if merged.has_conflicts() {
+        let conflicts = collect_conflicts(&repo, &merged)?;
+        write_conflicted_state(&repo, theirs.id())?;
+        return Ok(SquashMergeResult {
+            merged: false,
+            commit: None,
+            conflicts,
+        });
+    }
+
+    let tree_id = merged.write_tree_to(&repo).map_err(|e| e.message().to_string())?;
+    if tree_id == head.tree_id() {
+        return Ok(up_to_date);
+    }
+    let tree = repo.find_tree(tree_id).map_err(|e| e.message().to_string())?;
+    // Update the index and working tree while HEAD is still the checkout baseline
+    repo.checkout_tree(tree.as_object(), Some(CheckoutBuilder::new().safe()))
+        .map_err(|e| e.message().to_string())?;
+
+    let (author, committer, message) =
+        commit_identities(&repo, message, author.as_ref(), committer.as_ref(), co_author_agent)?;
+    let commit = write_commit(&repo, &author, &committer, &message, &tree, &[&head], sign)?;
+
+    Ok(SquashMergeResult {
+        merged: true,
+        commit: Some(commit.to_string()),
+        conflicts: Vec::new(),
+    })
+}
+
+// A region of a conflicting file, as 1-based start line and line count on each side.
+// A side with no lines in the region starts at the line that follows it.
+#[derive(Serialize)]
+pub(crate) struct ConflictRegion {
+    ours_start: u32,
+    ours_lines: u32,
+    theirs_start: u32,
+    theirs_lines: u32,
+}
+
+#[derive(Serialize)]
+pub(crate) struct ConflictPreview {
+    path: String,
+    // "content", "add_add", "modify_delete" or "binary"
+    kind: &'static str,
+    regions: Vec<ConflictRegion>,
+}
+
+#[derive(Serialize)]
+pub(crate) struct MergePreview {
+    clean: bool,
+    merge_base: String,
+    // Paths `theirs` changes that would merge without conflicts
+    clean_paths: Vec<String>,
+    conflicts: Vec<ConflictPreview>,
+}
+
+fn line_count(content: &[u8]) -> u32 {
+    let lines = content.iter().filter(|b| **b == b'\n').count();
+    let unterminated = content.last().is_some_and(|b| *b != b'\n');
+    (lines + unterminated as usize) as u32
+}
+
+// One change from the ancestor to a side, as 0-based half-open line ranges. Ancestor lines
+// are used to line the sides up, `start..end` locates the change in that side's version.
+struct LineChange {
+    ours: bool,
+    ancestor_start: u32,
+    ancestor_end: u32,
+    start: u32,
+    end: u32,
+}
+
+impl LineChange {
+    // From 1-based hunk header ranges. For an empty range (a pure insertion or deletion)
+    // libgit2 gives the line *before* it, for a non-empty one its first line.
+    fn from_hunk(ours: bool, old_start: u32, old_lines: u32, new_start: u32, new_lines: u32) -> Self {
+        let half_open = |start: u32, lines: u32| {
+            let start = if lines == 0 { start } else { start - 1 };
+            (start, start + lines)
+        };
+        let (ancestor_start, ancestor_end) = half_open(old_start, old_lines);
+        let (start, end) = half_open(new_start, new_lines);
+        LineChange {
+            ours,
+            ancestor_start,
+            ancestor_end,
+            start,
+            end,
+        }
+    }
+}
+
+fn changed_ranges(
+    repo: &Repository,
+    ancestor: &IndexEntry,
+    side: &IndexEntry,
+    ours: bool,
+) -> Result<Vec<LineChange>, String> {
+    let old = repo.find_blob(ancestor.id).map_err(|e| e.message().to_string())?;
+    let new = repo.find_blob(side.id).map_err(|e| e.message().to_string())?;
+    let mut opts = DiffOptions::new();
+    opts.context_lines(0);
+    let patch = Patch::from_blobs(&old, None, &new, None, Some(&mut opts))
+        .map_err(|e| e.message().to_string())?;
+
+    let mut changes = Vec::new();
+    for idx in 0..patch.num_hunks() {
+        let (hunk, _) = patch.hunk(idx).map_err(|e| e.message().to_string())?;
+        changes.push(LineChange::from_hunk(
+            ours,
+            hunk.old_start(),
+            hunk.old_lines(),
+            hunk.new_start(),
+            hunk.new_lines(),
+        ));
+    }
+    Ok(changes)
+}
+
+// Smallest range covering one side's changes in a group
+fn side_span(group: &[LineChange], ours: bool) -> Option<(u32, u32)> {
+    let changes = group.iter().filter(|c| c.ours == ours);
+    let start = changes.clone().map(|c| c.start).min()?;
+    let end = changes.map(|c| c.end).max()?;
+    Some((start + 1, end - start))
+}
+
+fn region_for(group: &[LineChange]) -> Option<ConflictRegion> {
+    let (ours_start, ours_lines) = side_span(group, true)?;
+    let (theirs_start, theirs_lines) = side_span(group, false)?;
+    Some(ConflictRegion {
+        ours_start,
+        ours_lines,
+        theirs_start,
+        theirs_lines,
+    })
+}
+
+// Groups the changes of both sides by overlapping (or touching) ancestor ranges, the way
+// xdiff's merge does; every group that contains changes from both sides is a conflict region.
+fn conflict_regions(mut changes: Vec<LineChange>) -> Vec<ConflictRegion> {
+    changes.sort_by_key(|c| c.ancestor_start);
+
+    let mut regions = Vec::new();
+    let mut group: Vec<LineChange> = Vec::new();
+    let mut group_end = 0;
+    for change in changes {
+        if !group.is_empty() && change.ancestor_start > group_end {
+            regions.extend(region_for(&group));
+            group.clear();
+        }
+        group_end = if group.is_empty() { change.ancestor_end } else { group_end.max(change.ancestor_end) };
+        group.push(change);
+    }
+    regions.extend(region_for(&group));
+    regions
+}
+
+fn preview_conflict(repo: &Repository, conflict: git2::IndexConflict) -> Result<ConflictPreview, String> {
+    let path = [&conflict.our, &conflict.their, &conflict.ancestor]
+        .into_iter()
+        .flatten()
+        .next()
+        .map(|entry| String::from_utf8_lossy(&entry.path).to_string())
+        .unwrap_or_default();
+
+    let whole_file = |entry: &IndexEntry| -> Result<u32, String> {
+        let blob = repo.find_blob(entry.id).map_err(|e| e.message().to_string())?;
+        Ok(line_count(blob.content()))
+    };
+    let is_binary = |entry: &Option<IndexEntry>| {
+        entry
+            .as_ref()
+            .and_then(|e| repo.find_blob(e.id).ok())
+            .is_some_and(|blob| blob.is_binary())
+    };
+
+    if is_binary(&conflict.ancestor) || is_binary(&conflict.our) || is_binary(&conflict.their) {
+        return Ok(ConflictPreview { path, kind: "binary", regions: Vec::new() });
+    }
+
+    let (kind, regions) = match (&conflict.ancestor, &conflict.our, &conflict.their) {
+        (Some(ancestor), Some(ours), Some(theirs)) => {
+            let mut changes = changed_ranges(repo, ancestor, ours, true)?;
+            changes.extend(changed_ranges(repo, ancestor, theirs, false)?);
+            ("content", conflict_regions(changes))
+        }
+        // Both sides added the file, or one side deleted what the other changed:
+        // the whole file is in conflict
+        (ancestor, ours, theirs) => {
+            let ours_lines = ours.as_ref().map(whole_file).transpose()?.unwrap_or(0);
+            let theirs_lines = theirs.as_ref().map(whole_file).transpose()?.unwrap_or(0);
+            let kind = if ancestor.is_none() { "add_add" } else { "modify_delete" };
+            let region = ConflictRegion {
+                ours_start: 1,
+                ours_lines,
+                theirs_start: 1,
+                theirs_lines,
+            };
+            (kind, vec![region])
+        }
+    };
+    Ok(ConflictPreview { path, kind, regions })
+}
+
+// Merges `theirs` into `ours` (both any revspec) entirely in memory and reports which paths
+// would conflict and where. Neither the index nor the working tree is touched.
+#[tauri::command]
+pub fn git_merge_preview(path: String, ours: String, theirs: String) -> Result<MergePreview, String> {
+    let repo = Repository::open(&path).map_err(|e| e.message().to_string())?;
+    let ours = resolve_commit(&repo, &ours)?;
+    let theirs = resolve_commit(&repo, &theirs)?;
+    let base_id = repo
+        .merge_base(ours.id(), theirs.id())
+        .map_err(|e| format!("No common ancestor: {}", e.message()))?;
+    let base = repo.find_commit(base_id).map_err(|e| e.message().to_string())?;
+
+    let base_tree = base.tree().map_err(|e| e.message().to_string())?;
+    let ours_tree = ours.tree().map_err(|e| e.message().to_string())?;
+    let theirs_tree = theirs.tree().map_err(|e| e.message().to_string())?;
+    let merged = repo
+        .merge_trees(&base_tree, &ours_tree, &theirs_tree, None)
+        .map_err(|e| e.message().to_string())?;
+
+    let mut conflicts = Vec::new();
+    for conflict in merged.conflicts().map_err(|e| e.message().to_string())? {
+        let conflict = conflict.map_err(|e| e.message().to_string())?;
+        conflicts.push(preview_conflict(&repo, conflict)?);
+    }
+
+    let incoming = repo
+        .diff_tree_to_tree(Some(&base_tree), Some(&theirs_tree), None)
+        .map_err(|e| e.message().to_string())?;
+    let clean_paths = incoming
+        .deltas()
+        .filter_map(|delta| delta.new_file().path().or_else(|| delta.old_file().path()))
+        .map(|p| p.to_string_lossy().to_string())
+        .filter(|p| !conflicts.iter().any(|c| &c.path == p))
+        .collect();
+
+    Ok(MergePreview {
+        clean: conflicts.is_empty(),
+        merge_base: base_id.to_string(),
+        clean_paths,
+        conflicts,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use git2::RepositoryState;
+
+    use super::*;
+    use crate::test_support::{commit_file, file_at, init_repo, path_str, TempDir};
+
+    fn switch(repo: &Repository, branch: &str) {
+        repo.set_head(&format!("refs/heads/{}", branch)).unwrap();
+        repo.checkout_head(Some(CheckoutBuilder::new().force())).unwrap();
+    }
+
+    // `main` and `feature` forked from a common base commit
+    fn forked(dir: &TempDir) -> Repository {
+        let repo = init_repo(&dir.join("repo"));
+        let base = commit_file(&repo, "shared.txt", "base\n", "Base");
+        repo.branch("feature", &repo.find_commit(base).unwrap(), false).unwrap();
+        repo
+    }
+
+    fn spans(regions: &[ConflictRegion]) -> Vec<(u32, u32, u32, u32)> {
+        regions
+            .iter()
+            .map(|r| (r.ours_start, r.ours_lines, r.theirs_start, r.theirs_lines))
+            .collect()
+    }
+
+    #[test]
+    fn insertion_touching_an_edit_is_one_region() {
+        // Ours inserts a line after line 2, theirs edits line 3
+        let changes = vec![
+            LineChange::from_hunk(true, 2, 0, 3, 1),
+            LineChange::from_hunk(false, 3, 1, 3, 1),
+        ];
+        assert_eq!(spans(&conflict_regions(changes)), vec![(3, 1, 3, 1)]);
+    }
+
+    #[test]
+    fn separate_changes_are_separate_regions() {
+        let changes = vec![
+            LineChange::from_hunk(true, 1, 1, 1, 1),
+            LineChange::from_hunk(false, 1, 1, 1, 2),
+            // Only ours touches line 4, so it is no conflict
+            LineChange::from_hunk(true, 4, 1, 4, 1),
+            // Theirs deletes line 7, ours edits lines 7-8
+            LineChange::from_hunk(false, 7, 1, 7, 0),
+            LineChange::from_hunk(true, 7, 2, 7, 2),
+        ];
+        assert_eq!(spans(&conflict_regions(changes)), vec![(1, 1, 1, 2), (7, 2, 8, 0)]);
+    }
+
+    #[test]
+    fn merge_preview_locates_touching_changes() {
+        let dir = TempDir::new("preview");
+        let repo = forked(&dir);
+        switch(&repo, "main");
+        commit_file(&repo, "lines.txt", "1\n2\n3\n4\n", "Lines");
+        let base = repo.head().unwrap().peel_to_commit().unwrap();
+        repo.branch("theirs", &base, false).unwrap();
+        let ours = commit_file(&repo, "lines.txt", "1\n2\nnew\n3\n4\n", "Insert after 2");
+        switch(&repo, "theirs");
+        commit_file(&repo, "lines.txt", "1\n2\nthree\n4\n", "Edit 3");
+
+        let preview = git_merge_preview(path_str(repo.workdir().unwrap()), ours.to_string(), "theirs".to_string())
+            .unwrap();
+
+        assert!(!preview.clean);
+        assert_eq!(preview.conflicts.len(), 1);
+        assert_eq!(preview.conflicts[0].kind, "content");
+        assert_eq!(spans(&preview.conflicts[0].regions), vec![(3, 1, 3, 1)]);
+    }
+
+    #[test]
+    fn clean_squash_merge_creates_single_parent_commit() {
+        let dir = TempDir::new("squash-merge");
+        let repo = forked(&dir);
+        switch(&repo, "feature");
+        commit_file(&repo, "one.txt", "1\n", "Feature one");
+        commit_file(&repo, "two.txt", "2\n", "Feature two");
+        switch(&repo, "main");
+        let main = commit_file(&repo, "main.txt", "main\n", "Main change");
+
+        let author = CommitIdentity {
+            name: "Step Author".to_string(),
+            email: "step@example.com".to_string(),
+        };
+        let result = git_squash_merge(
+            path_str(repo.workdir().unwrap()),
+            "feature".to_string(),
+            "Squash feature".to_string(),
+            Some(author),
+            None,
+            Some(false),
+            None,
+        )
+        .unwrap();
+
+        assert!(result.merged);
+        assert!(result.conflicts.is_empty());
+        let head = repo.head().unwrap().peel_to_commit().unwrap();
+        assert_eq!(result.commit, Some(head.id().to_string()));
+        assert_eq!(head.parent_ids().collect::<Vec<_>>(), vec![main]);
+        assert_eq!(head.message(), Some("Squash feature"));
+        assert_eq!(head.author().name(), Some("Step Author"));
+        assert_eq!(head.committer().name(), Some("Test User"));
+        for (file, content) in [("one.txt", "1\n"), ("two.txt", "2\n"), ("main.txt", "main\n")] {
+            assert_eq!(file_at(&repo, "HEAD", file).as_deref(), Some(content));
+        }
+        assert!(!has_tracked_changes(&repo).unwrap());
+    }
+
+    #[test]
+    fn conflicting_squash_merge_reports_conflicts_and_keeps_head() {
+        let dir = TempDir::new("squash-conflict");
+        let repo = forked(&dir);
+        switch(&repo, "feature");
+        commit_file(&repo, "shared.txt", "theirs\n", "Feature edit");
+        switch(&repo, "main");
+        let main = commit_file(&repo, "shared.txt", "ours\n", "Main edit");
+
+        let result = git_squash_merge(
+            path_str(repo.workdir().unwrap()),
+            "feature".to_string(),
+            "Squash feature".to_string(),
+            None,
+            None,
+            None,
+            None,
+        )
+        .unwrap();
+
+        assert!(!result.merged);
+        assert_eq!(result.commit, None);
+        assert_eq!(result.conflicts.len(), 1);
+        let conflict = &result.conflicts[0];
+        assert_eq!(conflict.path, "shared.txt");
+        assert_eq!(conflict.ancestor.as_deref(), Some("base\n"));
+        assert_eq!(conflict.ours.as_deref(), Some("ours\n"));
+        assert_eq!(conflict.theirs.as_deref(), Some("theirs\n"));
+
+        assert_eq!(repo.head().unwrap().target(), Some(main));
+        // Left like `git merge --squash`: conflicts in the index, but no MERGE_HEAD
+        let repo = Repository::open(repo.workdir().unwrap()).unwrap();
+        assert!(repo.index().unwrap().has_conflicts());
+        assert_eq!(repo.state(), RepositoryState::Clean);
+    }
+}
@@ -0,0 +1,595 @@
+// Fetch, pull and push through git2, reporting progress to the webview as it happens:
+// `git://progress` for object transfer, `git://sideband` for the remote's own messages
+// ("Counting objects...", hook output) and `git://push-rejected` for refused ref updates.
+
+use std::cell::RefCell;
+use std::time::{Duration, Instant};
+
+use git2::build::CheckoutBuilder;
+use git2::{
+    AutotagOption, BranchType, Commit, Direction, ErrorCode, FetchOptions, PushOptions,
+    RemoteCallbacks, Repository,
+};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Runtime};
+
+use super::git_auth::{credentials_callback, AuthLog};
+use super::git_rewrite::{linear_range, replay};
+use super::git_sign::{update_head, write_commit};
+use super::{commit_signature, get_git_status, GitStatus};
+
+const PROGRESS_EVENT: &str = "git://progress";
+const SIDEBAND_EVENT: &str = "git://sideband";
+const PUSH_REJECTED_EVENT: &str = "git://push-rejected";
+
+// Transfer progress fires for every object; the webview gets at most one update per interval
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+
+#[derive(Serialize, Clone)]
+struct RemoteProgress {
+    operation: &'static str,
+    remote: String,
+    received_objects: usize,
+    indexed_objects: usize,
+    total_objects: usize,
+    bytes: usize,
+}
+
+#[derive(Serialize, Clone)]
+struct SidebandMessage {
+    operation: &'static str,
+    remote: String,
+    message: String,
+}
+
+#[derive(Serialize, Clone)]
+struct PushRejection {
+    remote: String,
+    refname: String,
+    message: String,
+}
+
+#[derive(Deserialize, Clone, Copy, Default)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum PullStrategy {
+    // Replay local commits on top of the upstream, like `git pull --rebase`
+    #[default]
+    Rebase,
+    Merge,
+    FfOnly,
+}
+
+fn emit<R: Runtime, S: Serialize + Clone>(app: &AppHandle<R>, event: &str, payload: S) {
+    if let Err(e) = app.emit(event, payload) {
+        log::warn!("Failed to emit {}: {}", event, e);
+    }
+}
+
+fn remote_callbacks<'a, R: Runtime>(
+    app: &'a AppHandle<R>,
+    repo: &Repository,
+    operation: &'static str,
+    remote: &'a str,
+) -> Result<RemoteCallbacks<'a>, String> {
+    let config = repo.config().map_err(|e| e.message().to_string())?;
+    let mut callbacks = RemoteCallbacks::new();
+    callbacks.credentials(credentials_callback(config, AuthLog::default()));
+
+    let mut last_progress: Option<Instant> = None;
+    callbacks.transfer_progress(move |stats| {
+        let done = stats.indexed_objects() == stats.total_objects();
+        if done || last_progress.is_none_or(|t| t.elapsed() >= PROGRESS_INTERVAL) {
+            last_progress = Some(Instant::now());
+            emit(
+                app,
+                PROGRESS_EVENT,
+                RemoteProgress {
+                    operation,
+                    remote: remote.to_string(),
+                    received_objects: stats.received_objects(),
+                    indexed_objects: stats.indexed_objects(),
+                    total_objects: stats.total_objects(),
+                    bytes: stats.received_bytes(),
+                },
+            );
+        }
+        true
+    });
+
+    let mut last_push_progress: Option<Instant> = None;
+    callbacks.push_transfer_progress(move |current, total, bytes| {
+        if current == total || last_push_progress.is_none_or(|t| t.elapsed() >= PROGRESS_INTERVAL) {
+            last_push_progress = Some(Instant::now());
+            emit(
+                app,
+                PROGRESS_EVENT,
+                RemoteProgress {
+                    operation,
+                    remote: remote.to_string(),
+                    received_objects: current,
+                    indexed_objects: current,
+                    total_objects: total,
+                    bytes,
+                },
+            );
+        }
+    });
+
+    callbacks.sideband_progress(move |data| {
+        let message = String::from_utf8_lossy(data).trim().to_string();
+        if !message.is_empty() {
+            emit(
+                app,
+                SIDEBAND_EVENT,
+                SidebandMessage {
+                    operation,
+                    remote: remote.to_string(),
+                    message,
+                },
+            );
+        }
+        true
+    });
+    Ok(callbacks)
+}
+
+fn current_branch(repo: &Repository) -> Result<String, String> {
+    let head = repo.head().map_err(|_| "HEAD does not point to a branch".to_string())?;
+    if !head.is_branch() {
+        return Err("HEAD is detached".to_string());
+    }
+    head.shorthand()
+        .map(str::to_string)
+        .ok_or_else(|| "Branch name is not valid UTF-8".to_string())
+}
+
+// Remote the current branch tracks, falling back to "origin"
+fn default_remote(repo: &Repository) -> String {
+    current_branch(repo)
+        .ok()
+        .and_then(|branch| repo.branch_upstream_remote(&format!("refs/heads/{}", branch)).ok())
+        .and_then(|buf| buf.as_str().map(str::to_string))
+        .unwrap_or_else(|| "origin".to_string())
+}
+
+fn fetch_remote<R: Runtime>(app: &AppHandle<R>, repo: &Repository, remote_name: &str) -> Result<(), String> {
+    let mut remote = repo
+        .find_remote(remote_name)
+        .map_err(|e| format!("Unknown remote '{}': {}", remote_name, e.message()))?;
+    let mut opts = FetchOptions::new();
+    opts.remote_callbacks(remote_callbacks(app, repo, "fetch", remote_name)?)
+        .download_tags(AutotagOption::Auto);
+    // An empty refspec list uses the remote's configured fetch refspecs
+    remote
+        .fetch::<&str>(&[], Some(&mut opts), None)
+        .map_err(|e| e.message().to_string())
+}
+
+fn fetch_blocking<R: Runtime>(app: &AppHandle<R>, path: &str, remote: Option<String>) -> Result<GitStatus, String> {
+    let repo = Repository::open(path).map_err(|e| e.message().to_string())?;
+    let remote = remote.unwrap_or_else(|| default_remote(&repo));
+    fetch_remote(app, &repo, &remote)?;
+    get_git_status(path.to_string(), None)
+}
+
+// Moves the working tree and index to `target`, then HEAD (expected at `from`).
+// Refuses to overwrite local changes.
+fn move_to(repo: &Repository, from: &Commit, target: &Commit, reflog: &str) -> Result<(), String> {
+    let tree = target.tree().map_err(|e| e.message().to_string())?;
+    repo.checkout_tree(tree.as_object(), Some(CheckoutBuilder::new().safe()))
+        .map_err(|e| format!("Cannot update the working tree: {}", e.message()))?;
+    update_head(repo, target.id(), Some(from.id()), reflog)
+}
+
+fn pull_blocking<R: Runtime>(app: &AppHandle<R>, path: &str, strategy: PullStrategy) -> Result<GitStatus, String> {
+    let repo = Repository::open(path).map_err(|e| e.message().to_string())?;
+    let branch_name = current_branch(&repo)?;
+    let remote_name = repo
+        .branch_upstream_remote(&format!("refs/heads/{}", branch_name))
+        .ok()
+        .and_then(|buf| buf.as_str().map(str::to_string))
+        .ok_or_else(|| format!("Branch '{}' has no upstream", branch_name))?;
+    fetch_remote(app, &repo, &remote_name)?;
+
+    let branch = repo
+        .find_branch(&branch_name, BranchType::Local)
+        .map_err(|e| e.message().to_string())?;
+    let upstream = branch
+        .upstream()
+        .map_err(|e| e.message().to_string())?
+        .into_reference();
+    let upstream_name = upstream.shorthand().unwrap_or("upstream").to_string();
+    let theirs = upstream.peel_to_commit().map_err(|e| e.message().to_string())?;
+    let ours = branch.get().peel_to_commit().map_err(|e| e.message().to_string())?;
+
+    let descendant_of = |a: &Commit, b: &Commit| {
+        repo.graph_descendant_of(a.id(), b.id())
+            .map_err(|e| e.message().to_string())
+    };
+    if ours.id() == theirs.id() || descendant_of(&ours, &theirs)? {
+        return get_git_status(path.to_string(), None);
+    }
+    if descendant_of(&theirs, &ours)? {
+        move_to(&repo, &ours, &theirs, &format!("pull: Fast-forward to {}", upstream_name))?;
+        return get_git_status(path.to_string(), None);
+    }
+
+    match strategy {
+        PullStrategy::FfOnly => {
+            return Err(format!("Cannot fast-forward: '{}' and '{}' have diverged", branch_name, upstream_name))
+        }
+        PullStrategy::Rebase => {
+            let base = repo
+                .merge_base(ours.id(), theirs.id())
+                .map_err(|e| e.message().to_string())?;
+            let local = linear_range(&repo, base, ours.id())?;
+            let count = local.len();
+            let rebased = replay(&repo, &local, theirs)?;
+            move_to(
+                &repo,
+                &ours,
+                &rebased,
+                &format!("pull --rebase: {} commit(s) onto {}", count, upstream_name),
+            )?;
+        }
+        PullStrategy::Merge => {
+            let mut merged = repo
+                .merge_commits(&ours, &theirs, None)
+                .map_err(|e| e.message().to_string())?;
+            if merged.has_conflicts() {
+                let paths: Vec<String> = merged
+                    .conflicts()
+                    .map_err(|e| e.message().to_string())?
+                    .flatten()
+                    .filter_map(|c| c.our.or(c.their).map(|e| String::from_utf8_lossy(&e.path).to_string()))
+                    .collect();
+                return Err(format!("Merge with {} conflicts in: {}", upstream_name, paths.join(", ")));
+            }
+            let tree_id = merged.write_tree_to(&repo).map_err(|e| e.message().to_string())?;
+            let tree = repo.find_tree(tree_id).map_err(|e| e.message().to_string())?;
+            repo.checkout_tree(tree.as_object(), Some(CheckoutBuilder::new().safe()))
+                .map_err(|e| format!("Cannot update the working tree: {}", e.message()))?;
+            let signature = commit_signature(&repo)?;
+            let message = format!("Merge remote-tracking branch '{}' into {}", upstream_name, branch_name);
+            write_commit(&repo, &signature, &signature, &message, &tree, &[&ours, &theirs], None)?;
+        }
+    }
+    get_git_status(path.to_string(), None)
+}
+
+// `main` -> `refs/heads/main`, `v1.0` -> `refs/tags/v1.0`, `HEAD` -> the current branch.
+// Names that don't resolve locally (a new branch on the remote side) are taken as branches;
+// empty names (deletions) stay empty.
+fn full_refname(repo: &Repository, name: &str) -> String {
+    if name.is_empty() || name.starts_with("refs/") {
+        return name.to_string();
+    }
+    repo.resolve_reference_from_short_name(name)
+        .and_then(|reference| reference.resolve())
+        .ok()
+        .and_then(|reference| reference.name().map(str::to_string))
+        .filter(|full| full.starts_with("refs/heads/") || full.starts_with("refs/tags/"))
+        .unwrap_or_else(|| format!("refs/heads/{}", name))
+}
+
+fn push_blocking<R: Runtime>(
+    app: &AppHandle<R>,
+    path: &str,
+    remote: Option<String>,
+    refspec: Option<String>,
+    force_with_lease: bool,
+) -> Result<GitStatus, String> {
+    let repo = Repository::open(path).map_err(|e| e.message().to_string())?;
+    let remote_name = remote.unwrap_or_else(|| default_remote(&repo));
+    let refspec = match refspec {
+        Some(spec) => spec,
+        None => {
+            let branch = current_branch(&repo)?;
+            let local = format!("refs/heads/{}", branch);
+            // Push to the tracked branch when there is one, else to the same name
+            let target = repo
+                .config()
+                .and_then(|config| config.get_string(&format!("branch.{}.merge", branch)))
+                .unwrap_or_else(|_| local.clone());
+            format!("{}:{}", local, target)
+        }
+    };
+    // libgit2 only takes full reference names; expand short ones the way `git push` does
+    let forced = refspec.starts_with('+');
+    let spec = refspec.trim_start_matches('+');
+    let (source, destination) = match spec.split_once(':') {
+        Some((src, dst)) => (full_refname(&repo, src), full_refname(&repo, dst)),
+        None => {
+            let src = full_refname(&repo, spec);
+            (src.clone(), src)
+        }
+    };
+    let spec = format!("{}:{}", source, destination);
+    let refspec = if forced { format!("+{}", spec) } else { spec.clone() };
+
+    let mut remote = repo
+        .find_remote(&remote_name)
+        .map_err(|e| format!("Unknown remote '{}': {}", remote_name, e.message()))?;
+
+    let refspec = if force_with_lease {
+        // Only force when the remote ref is still where our last fetch saw it
+        let tracking = format!(
+            "refs/remotes/{}/{}",
+            remote_name,
+            destination.strip_prefix("refs/heads/").unwrap_or(&destination)
+        );
+        let expected = repo.refname_to_id(&tracking).ok();
+        let connection = remote
+            .connect_auth(Direction::Push, Some(remote_callbacks(app, &repo, "push", &remote_name)?), None)
+            .map_err(|e| e.message().to_string())?;
+        let actual = connection
+            .list()
+            .map_err(|e| e.message().to_string())?
+            .iter()
+            .find(|head| head.name() == destination)
+            .map(|head| head.oid());
+        drop(connection);
+        if actual != expected {
+            return Err(format!(
+                "Stale info: {} on {} is at {}, expected {}. Fetch first.",
+                destination,
+                remote_name,
+                actual.map_or("nothing".to_string(), |oid| oid.to_string()),
+                expected.map_or("nothing".to_string(), |oid| oid.to_string()),
+            ));
+        }
+        format!("+{}", spec)
+    } else {
+        refspec
+    };
+
+    let rejections = RefCell::new(Vec::new());
+    let mut callbacks = remote_callbacks(app, &repo, "push", &remote_name)?;
+    callbacks.push_update_reference(|refname, status| {
+        if let Some(message) = status {
+            let rejection = PushRejection {
+                remote: remote_name.clone(),
+                refname: refname.to_string(),
+                message: message.to_string(),
+            };
+            emit(app, PUSH_REJECTED_EVENT, rejection);
+            rejections.borrow_mut().push(format!("{} ({})", refname, message));
+        }
+        Ok(())
+    });
+    let mut opts = PushOptions::new();
+    opts.remote_callbacks(callbacks);
+    let pushed = remote.push(&[refspec.as_str()], Some(&mut opts));
+    drop(opts);
+    if let Err(e) = pushed {
+        // libgit2 refuses non-fast-forward updates itself, before the remote is asked, so
+        // those never reach push_update_reference
+        if e.code() != ErrorCode::NotFastForward {
+            return Err(e.message().to_string());
+        }
+        let message = if e.message().contains("not present locally") {
+            "fetch first"
+        } else {
+            "non-fast-forward"
+        };
+        let rejection = PushRejection {
+            remote: remote_name.clone(),
+            refname: destination.clone(),
+            message: message.to_string(),
+        };
+        emit(app, PUSH_REJECTED_EVENT, rejection);
+        return Err(format!("Push rejected: {} ({})", destination, message));
+    }
+
+    let rejections = rejections.into_inner();
+    if !rejections.is_empty() {
+        return Err(format!("Push rejected: {}", rejections.join(", ")));
+    }
+    get_git_status(path.to_string(), None)
+}
+
+// Fetches `remote` (default: the current branch's upstream remote, else origin) and returns
+// the refreshed status, including ahead/behind
+#[tauri::command]
+pub async fn git_fetch(app: AppHandle, path: String, remote: Option<String>) -> Result<GitStatus, String> {
+    tauri::async_runtime::spawn_blocking(move || fetch_blocking(&app, &path, remote))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+// Fetches the upstream and integrates it: rebase (default), merge or ff-only.
+// Conflicts abort the pull before anything is changed.
+#[tauri::command]
+pub async fn git_pull(
+    app: AppHandle,
+    path: String,
+    strategy: Option<PullStrategy>,
+) -> Result<GitStatus, String> {
+    let strategy = strategy.unwrap_or_default();
+    tauri::async_runtime::spawn_blocking(move || pull_blocking(&app, &path, strategy))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+// Pushes `refspec` (default: the current branch to its upstream) to `remote`.
+// `force_with_lease` force-pushes only if the remote ref still matches our tracking ref.
+#[tauri::command]
+pub async fn git_push(
+    app: AppHandle,
+    path: String,
+    remote: Option<String>,
+    refspec: Option<String>,
+    force_with_lease: Option<bool>,
+) -> Result<GitStatus, String> {
+    let force_with_lease = force_with_lease.unwrap_or(false);
+    tauri::async_runtime::spawn_blocking(move || {
+        push_blocking(&app, &path, remote, refspec, force_with_lease)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use tauri::test::{mock_app, MockRuntime};
+
+    use super::*;
+    use crate::test_support::{clone_repo, commit_file, init_bare, init_repo, path_str, TempDir};
+
+    // Two clones of one bare remote, both on `main` tracking origin/main
+    struct Remotes {
+        _dir: TempDir,
+        remote: Repository,
+        ours: Repository,
+        theirs: Repository,
+    }
+
+    impl Remotes {
+        fn new(app: &AppHandle<MockRuntime>) -> Self {
+            let dir = TempDir::new("remote");
+            let remote_path = dir.join("remote.git");
+            let remote = init_bare(&remote_path);
+
+            let ours = init_repo(&dir.join("ours"));
+            commit_file(&ours, "README.md", "hello\n", "Initial commit");
+            ours.remote("origin", remote_path.to_str().unwrap()).unwrap();
+            push_blocking(app, &workdir(&ours), None, Some("refs/heads/main:refs/heads/main".into()), false)
+                .unwrap();
+            ours.find_branch("main", BranchType::Local)
+                .unwrap()
+                .set_upstream(Some("origin/main"))
+                .unwrap();
+
+            let theirs = clone_repo(&remote_path, &dir.join("theirs"));
+            Remotes {
+                _dir: dir,
+                remote,
+                ours,
+                theirs,
+            }
+        }
+
+        // One new commit on each side: theirs pushed, ours local only
+        fn diverge(&self, app: &AppHandle<MockRuntime>) {
+            commit_file(&self.theirs, "theirs.txt", "theirs\n", "Their change");
+            push_blocking(app, &workdir(&self.theirs), None, None, false).unwrap();
+            commit_file(&self.ours, "ours.txt", "ours\n", "Our change");
+        }
+
+        fn remote_main(&self) -> git2::Oid {
+            self.remote.refname_to_id("refs/heads/main").unwrap()
+        }
+    }
+
+    fn workdir(repo: &Repository) -> String {
+        path_str(repo.workdir().unwrap())
+    }
+
+    fn head(repo: &Repository) -> Commit<'_> {
+        repo.head().unwrap().peel_to_commit().unwrap()
+    }
+
+    #[test]
+    fn fetch_updates_ahead_and_behind() {
+        let app = mock_app();
+        let remotes = Remotes::new(app.handle());
+        remotes.diverge(app.handle());
+
+        let before = get_git_status(workdir(&remotes.ours), None).unwrap();
+        assert_eq!((before.ahead, before.behind), (1, 0));
+
+        let status = fetch_blocking(app.handle(), &workdir(&remotes.ours), None).unwrap();
+        assert_eq!((status.ahead, status.behind), (1, 1));
+    }
+
+    #[test]
+    fn ff_only_pull_refuses_diverged_branches() {
+        let app = mock_app();
+        let remotes = Remotes::new(app.handle());
+        remotes.diverge(app.handle());
+        let before = head(&remotes.ours).id();
+
+        let err = pull_blocking(app.handle(), &workdir(&remotes.ours), PullStrategy::FfOnly)
+            .err()
+            .expect("ff-only pull of diverged branches");
+        assert!(err.contains("diverged"), "{}", err);
+        assert_eq!(head(&remotes.ours).id(), before);
+    }
+
+    #[test]
+    fn rebase_pull_replays_local_commits() {
+        let app = mock_app();
+        let remotes = Remotes::new(app.handle());
+        remotes.diverge(app.handle());
+
+        let status = pull_blocking(app.handle(), &workdir(&remotes.ours), PullStrategy::Rebase).unwrap();
+        assert_eq!((status.ahead, status.behind), (1, 0));
+        assert!(status.files.is_empty());
+
+        let rebased = head(&remotes.ours);
+        assert_eq!(rebased.summary(), Some("Our change"));
+        assert_eq!(rebased.parent_count(), 1);
+        assert_eq!(rebased.parent_id(0).unwrap(), remotes.remote_main());
+        let root = remotes.ours.workdir().unwrap();
+        assert!(root.join("ours.txt").is_file() && root.join("theirs.txt").is_file());
+    }
+
+    #[test]
+    fn push_rejects_non_fast_forward() {
+        let app = mock_app();
+        let remotes = Remotes::new(app.handle());
+        remotes.diverge(app.handle());
+        let remote_main = remotes.remote_main();
+
+        // Their commit is not even known locally yet
+        let err = push_blocking(app.handle(), &workdir(&remotes.ours), None, None, false)
+            .err()
+            .expect("non-fast-forward push");
+        assert!(err.contains("Push rejected") && err.contains("fetch first"), "{}", err);
+
+        fetch_blocking(app.handle(), &workdir(&remotes.ours), None).unwrap();
+        let err = push_blocking(app.handle(), &workdir(&remotes.ours), None, None, false)
+            .err()
+            .expect("non-fast-forward push");
+        assert!(err.contains("Push rejected") && err.contains("non-fast-forward"), "{}", err);
+        assert_eq!(remotes.remote_main(), remote_main);
+    }
+
+    #[test]
+    fn force_with_lease_refuses_stale_tracking_ref() {
+        let app = mock_app();
+        let remotes = Remotes::new(app.handle());
+        remotes.diverge(app.handle());
+        let theirs_pushed = remotes.remote_main();
+        let ours: PathBuf = remotes.ours.workdir().unwrap().to_path_buf();
+
+        // origin/main still points at the initial commit, so the lease is stale
+        let err = push_blocking(app.handle(), &path_str(&ours), None, None, true)
+            .err()
+            .expect("push with a stale lease");
+        assert!(err.contains("Stale info"), "{}", err);
+        assert_eq!(remotes.remote_main(), theirs_pushed);
+
+        // Once their commit has been seen, overwriting it is deliberate
+        fetch_blocking(app.handle(), &path_str(&ours), None).unwrap();
+        push_blocking(app.handle(), &path_str(&ours), None, None, true).unwrap();
+        assert_eq!(remotes.remote_main(), head(&remotes.ours).id());
+    }
+
+    #[test]
+    fn force_with_lease_accepts_short_refspecs() {
+        let app = mock_app();
+        let remotes = Remotes::new(app.handle());
+        remotes.diverge(app.handle());
+        let ours = workdir(&remotes.ours);
+        fetch_blocking(app.handle(), &ours, None).unwrap();
+
+        for refspec in ["main", "main:main", "HEAD"] {
+            push_blocking(app.handle(), &ours, None, Some(refspec.to_string()), true)
+                .unwrap_or_else(|e| panic!("push {}: {}", refspec, e));
+            assert_eq!(remotes.remote_main(), head(&remotes.ours).id());
+        }
+    }
+}
@@ -0,0 +1,309 @@
+// History rewriting for auto-checkpoints: amend HEAD, fold staged changes into an earlier
+// commit, or collapse a task's checkpoint commits into one. Rewritten commits keep their
+// author and get the current identity as committer; HEAD only moves once the whole new
+// chain has been written, so a failure leaves the branch untouched.
+
+use git2::{Commit, Oid, Repository, Sort, Tree};
+
+use super::{commit_signature, resolve_commit};
+use super::git_sign::{create_commit, update_head};
+
+fn head_commit(repo: &Repository) -> Result<Commit<'_>, String> {
+    repo.head()
+        .and_then(|head| head.peel_to_commit())
+        .map_err(|_| "HEAD is not pointing to a commit".to_string())
+}
+
+fn index_tree(repo: &Repository) -> Result<Tree<'_>, String> {
+    let mut index = repo.index().map_err(|e| e.message().to_string())?;
+    if index.has_conflicts() {
+        return Err("Index has unresolved conflicts".to_string());
+    }
+    let tree_id = index.write_tree().map_err(|e| e.message().to_string())?;
+    repo.find_tree(tree_id).map_err(|e| e.message().to_string())
+}
+
+// Commits after `base` up to and including `tip`, oldest first. The range has to be a
+// straight line of single-parent commits starting right after `base`.
+pub(crate) fn linear_range<'r>(repo: &'r Repository, base: Oid, tip: Oid) -> Result<Vec<Commit<'r>>, String> {
+    if base != tip
+        && !repo
+            .graph_descendant_of(tip, base)
+            .map_err(|e| e.message().to_string())?
+    {
+        return Err(format!("{} is not an ancestor of {}", base, tip));
+    }
+    let mut walk = repo.revwalk().map_err(|e| e.message().to_string())?;
+    walk.set_sorting(Sort::TOPOLOGICAL | Sort::REVERSE)
+        .map_err(|e| e.message().to_string())?;
+    walk.push(tip).map_err(|e| e.message().to_string())?;
+    walk.hide(base).map_err(|e| e.message().to_string())?;
+
+    let mut commits = Vec::new();
+    let mut expected_parent = base;
+    for oid in walk {
+        let commit = repo
+            .find_commit(oid.map_err(|e| e.message().to_string())?)
+            .map_err(|e| e.message().to_string())?;
+        if commit.parent_count() != 1 || commit.parent_id(0).ok() != Some(expected_parent) {
+            return Err(format!("Cannot rewrite across merge commit {}", commit.id()));
+        }
+        expected_parent = commit.id();
+        commits.push(commit);
+    }
+    Ok(commits)
+}
+
+// Re-creates `commits` on top of `onto`, cherry-pick style, entirely in memory.
+// Returns the new tip.
+pub(crate) fn replay<'r>(repo: &'r Repository, commits: &[Commit<'r>], onto: Commit<'r>) -> Result<Commit<'r>, String> {
+    let committer = commit_signature(repo)?;
+    let mut tip = onto;
+    for commit in commits {
+        let parent = commit.parent(0).map_err(|e| e.message().to_string())?;
+        let tree = if parent.tree_id() == tip.tree_id() {
+            commit.tree().map_err(|e| e.message().to_string())?
+        } else {
+            let parent_tree = parent.tree().map_err(|e| e.message().to_string())?;
+            let tip_tree = tip.tree().map_err(|e| e.message().to_string())?;
+            let commit_tree = commit.tree().map_err(|e| e.message().to_string())?;
+            let mut merged = repo
+                .merge_trees(&parent_tree, &tip_tree, &commit_tree, None)
+                .map_err(|e| e.message().to_string())?;
+            if merged.has_conflicts() {
+                return Err(format!("Replaying {} would conflict", commit.id()));
+            }
+            let tree_id = merged.write_tree_to(repo).map_err(|e| e.message().to_string())?;
+            repo.find_tree(tree_id).map_err(|e| e.message().to_string())?
+        };
+        let message = commit.message().unwrap_or("");
+        let oid = create_commit(repo, &commit.author(), &committer, message, &tree, &[&tip], None)?;
+        tip = repo.find_commit(oid).map_err(|e| e.message().to_string())?;
+    }
+    Ok(tip)
+}
+
+// Replaces HEAD with a commit of the current index, keeping HEAD's author and parents.
+// Without `message` the original message is kept.
+#[tauri::command]
+pub fn git_amend(path: String, message: Option<String>, sign: Option<bool>) -> Result<String, String> {
+    let repo = Repository::open(&path).map_err(|e| e.message().to_string())?;
+    let head = head_commit(&repo).map_err(|_| "Nothing to amend".to_string())?;
+    let tree = index_tree(&repo)?;
+    let parents: Vec<Commit> = head.parents().collect();
+    let parent_refs: Vec<&Commit> = parents.iter().collect();
+
+    let message = message.unwrap_or_else(|| head.message().unwrap_or("").to_string());
+    let committer = commit_signature(&repo)?;
+    let oid = create_commit(&repo, &head.author(), &committer, &message, &tree, &parent_refs, sign)?;
+    let summary = message.lines().next().unwrap_or("");
+    update_head(&repo, oid, Some(head.id()), &format!("commit (amend): {}", summary))?;
+    Ok(oid.to_string())
+}
+
+// Folds the staged changes into `target_oid` (HEAD or one of its ancestors) and replays
+// the commits after it. Like `git commit --fixup` + autosquash, in one step.
+#[tauri::command]
+pub fn git_fixup(path: String, target_oid: String) -> Result<String, String> {
+    let repo = Repository::open(&path).map_err(|e| e.message().to_string())?;
+    let head = head_commit(&repo)?;
+    let target = resolve_commit(&repo, &target_oid)?;
+    let staged = index_tree(&repo)?;
+    if staged.id() == head.tree_id() {
+        return Err("No staged changes to fold in".to_string());
+    }
+    if target.parent_count() > 1 {
+        return Err("Cannot fix up a merge commit".to_string());
+    }
+    let later = linear_range(&repo, target.id(), head.id())?;
+
+    // Apply the staged diff (HEAD -> index) to the target's tree
+    let head_tree = head.tree().map_err(|e| e.message().to_string())?;
+    let target_tree = target.tree().map_err(|e| e.message().to_string())?;
+    let mut merged = repo
+        .merge_trees(&head_tree, &target_tree, &staged, None)
+        .map_err(|e| e.message().to_string())?;
+    if merged.has_conflicts() {
+        return Err(format!("Staged changes do not apply cleanly to {}", target.id()));
+    }
+    let fixed_tree_id = merged.write_tree_to(&repo).map_err(|e| e.message().to_string())?;
+    let fixed_tree = repo.find_tree(fixed_tree_id).map_err(|e| e.message().to_string())?;
+
+    let parents: Vec<Commit> = target.parents().collect();
+    let parent_refs: Vec<&Commit> = parents.iter().collect();
+    let committer = commit_signature(&repo)?;
+    let message = target.message().unwrap_or("");
+    let fixed_id = create_commit(&repo, &target.author(), &committer, message, &fixed_tree, &parent_refs, None)?;
+    let fixed = repo.find_commit(fixed_id).map_err(|e| e.message().to_string())?;
+
+    let new_head = replay(&repo, &later, fixed)?;
+    // The rewritten branch must end up exactly where the index already is
+    if new_head.tree_id() != staged.id() {
+        return Err("Fixup would change the resulting tree; aborting".to_string());
+    }
+    update_head(&repo, new_head.id(), Some(head.id()), &format!("rebase (fixup): onto {}", target.id()))?;
+    Ok(new_head.id().to_string())
+}
+
+// Collapses the commits from `from` to `to` (both inclusive, `to` defaults to HEAD) into a
+// single commit with `message`, then replays anything after `to` on top of it.
+#[tauri::command]
+pub fn git_squash_range(
+    path: String,
+    from: String,
+    to: Option<String>,
+    message: String,
+    sign: Option<bool>,
+) -> Result<String, String> {
+    let repo = Repository::open(&path).map_err(|e| e.message().to_string())?;
+    let head = head_commit(&repo)?;
+    let first = resolve_commit(&repo, &from)?;
+    let last = match &to {
+        Some(spec) => resolve_commit(&repo, spec)?,
+        None => head.clone(),
+    };
+    if first.parent_count() != 1 {
+        return Err(format!("Cannot squash from {}: it must have exactly one parent", first.id()));
+    }
+    let base = first.parent(0).map_err(|e| e.message().to_string())?;
+
+    let squashed_range = linear_range(&repo, base.id(), last.id())?;
+    let later = linear_range(&repo, last.id(), head.id())?;
+    if squashed_range.len() < 2 {
+        return Err("Nothing to squash: the range holds a single commit".to_string());
+    }
+
+    let tree = last.tree().map_err(|e| e.message().to_string())?;
+    let committer = commit_signature(&repo)?;
+    let squashed_id = create_commit(&repo, &first.author(), &committer, &message, &tree, &[&base], sign)?;
+    let squashed = repo.find_commit(squashed_id).map_err(|e| e.message().to_string())?;
+
+    let new_head = replay(&repo, &later, squashed)?;
+    if new_head.tree_id() != head.tree_id() {
+        return Err("Squash would change the resulting tree; aborting".to_string());
+    }
+    update_head(
+        &repo,
+        new_head.id(),
+        Some(head.id()),
+        &format!("rebase (squash): {} commits into {}", squashed_range.len(), squashed_id),
+    )?;
+    Ok(squashed_id.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use git2::Signature;
+
+    use super::*;
+    use crate::test_support::{commit_file, file_at, init_repo, path_str, stage_file, TempDir};
+
+    fn head_id(repo: &Repository) -> Oid {
+        repo.head().unwrap().target().unwrap()
+    }
+
+    fn summary(repo: &Repository, spec: &str) -> String {
+        resolve_commit(repo, spec).unwrap().summary().unwrap().to_string()
+    }
+
+    #[test]
+    fn fixup_folds_into_earlier_commit_and_replays_later_ones() {
+        let dir = TempDir::new("fixup");
+        let repo = init_repo(&dir.join("repo"));
+        let base = commit_file(&repo, "README.md", "hello\n", "Base");
+        commit_file(&repo, "a.txt", "a\n", "Target");
+        commit_file(&repo, "b.txt", "b\n", "Later 1");
+        commit_file(&repo, "c.txt", "c\n", "Later 2");
+
+        stage_file(&repo, "a.txt", "fixed\n");
+        let new_head = git_fixup(path_str(repo.workdir().unwrap()), "HEAD~2".to_string()).unwrap();
+
+        assert_eq!(head_id(&repo).to_string(), new_head);
+        assert_eq!(resolve_commit(&repo, "HEAD~3").unwrap().id(), base);
+        assert_eq!(summary(&repo, "HEAD~2"), "Target");
+        assert_eq!(summary(&repo, "HEAD~1"), "Later 1");
+        assert_eq!(summary(&repo, "HEAD"), "Later 2");
+        assert_eq!(file_at(&repo, "HEAD~2", "a.txt").as_deref(), Some("fixed\n"));
+        assert_eq!(file_at(&repo, "HEAD~2", "b.txt"), None);
+        assert_eq!(file_at(&repo, "HEAD", "c.txt").as_deref(), Some("c\n"));
+    }
+
+    #[test]
+    fn squash_range_keeps_later_commits() {
+        let dir = TempDir::new("squash");
+        let repo = init_repo(&dir.join("repo"));
+        let base = commit_file(&repo, "README.md", "hello\n", "Base");
+        let first = commit_file(&repo, "one.txt", "1\n", "One");
+        commit_file(&repo, "two.txt", "2\n", "Two");
+        let last = commit_file(&repo, "three.txt", "3\n", "Three");
+        commit_file(&repo, "four.txt", "4\n", "Four");
+        let old_tree = resolve_commit(&repo, "HEAD").unwrap().tree_id();
+
+        let squashed = git_squash_range(
+            path_str(repo.workdir().unwrap()),
+            first.to_string(),
+            Some(last.to_string()),
+            "Squashed".to_string(),
+            None,
+        )
+        .unwrap();
+
+        let head = resolve_commit(&repo, "HEAD").unwrap();
+        assert_eq!(head.summary(), Some("Four"));
+        assert_eq!(head.tree_id(), old_tree);
+        assert_eq!(head.parent_id(0).unwrap().to_string(), squashed);
+        let squashed = resolve_commit(&repo, &squashed).unwrap();
+        assert_eq!(squashed.message(), Some("Squashed"));
+        assert_eq!(squashed.parent_ids().collect::<Vec<_>>(), vec![base]);
+        assert_eq!(file_at(&repo, "HEAD~1", "three.txt").as_deref(), Some("3\n"));
+        assert_eq!(file_at(&repo, "HEAD~1", "four.txt"), None);
+    }
+
+    #[test]
+    fn refuses_to_rewrite_across_merge_commit() {
+        let dir = TempDir::new("merge");
+        let repo = init_repo(&dir.join("repo"));
+        let base = commit_file(&repo, "README.md", "hello\n", "Base");
+        let base = repo.find_commit(base).unwrap();
+        let signature = Signature::now("Test User", "test@example.com").unwrap();
+        let side_id = repo
+            .commit(None, &signature, &signature, "Side", &base.tree().unwrap(), &[&base])
+            .unwrap();
+        let side = repo.find_commit(side_id).unwrap();
+        let before_merge = commit_file(&repo, "a.txt", "a\n", "Main");
+        let main = repo.find_commit(before_merge).unwrap();
+        repo.commit(Some("HEAD"), &signature, &signature, "Merge", &main.tree().unwrap(), &[&main, &side])
+            .unwrap();
+        commit_file(&repo, "b.txt", "b\n", "After merge");
+        let path = path_str(repo.workdir().unwrap());
+        let old_head = head_id(&repo);
+
+        let err = git_squash_range(path.clone(), before_merge.to_string(), None, "Squashed".to_string(), None)
+            .unwrap_err();
+        assert!(err.starts_with("Cannot rewrite across merge commit"), "{}", err);
+
+        stage_file(&repo, "a.txt", "fixed\n");
+        let err = git_fixup(path, before_merge.to_string()).unwrap_err();
+        assert!(err.starts_with("Cannot rewrite across merge commit"), "{}", err);
+        assert_eq!(head_id(&repo), old_head);
+    }
+
+    #[test]
+    fn fixup_aborts_when_the_result_would_differ_from_the_index() {
+        let dir = TempDir::new("abort");
+        let repo = init_repo(&dir.join("repo"));
+        commit_file(&repo, "README.md", "hello\n", "Base");
+        let target = commit_file(&repo, "x.txt", "a\n", "Target");
+        commit_file(&repo, "x.txt", "b\n", "Later");
+        let old_head = head_id(&repo);
+
+        // Reverting the later change can't be folded into the target: replaying "Later"
+        // on top of it brings the change right back
+        stage_file(&repo, "x.txt", "a\n");
+        let err = git_fixup(path_str(repo.workdir().unwrap()), target.to_string()).unwrap_err();
+
+        assert_eq!(err, "Fixup would change the resulting tree; aborting");
+        assert_eq!(head_id(&repo), old_head);
+        assert_eq!(file_at(&repo, "HEAD", "x.txt").as_deref(), Some("b\n"));
+    }
+}
@@ -0,0 +1,215 @@
+// Commit signing for the native commit path. Follows the same git config as the CLI:
+// `commit.gpgsign`, `gpg.format` (openpgp, x509 or ssh), `user.signingkey` and the
+// `gpg.program` / `gpg.<format>.program` overrides.
+
+use std::fs;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use git2::{Commit, Config, ErrorCode, Oid, Repository, Signature, Tree};
+
+#[derive(Clone, Copy, PartialEq)]
+enum SigningFormat {
+    OpenPgp,
+    X509,
+    Ssh,
+}
+
+struct SigningConfig {
+    format: SigningFormat,
+    program: String,
+    key: Option<String>,
+}
+
+impl SigningConfig {
+    fn from_config(config: &Config) -> Result<Self, String> {
+        let format = match config.get_string("gpg.format").as_deref() {
+            Ok("openpgp") | Err(_) => SigningFormat::OpenPgp,
+            Ok("x509") => SigningFormat::X509,
+            Ok("ssh") => SigningFormat::Ssh,
+            Ok(other) => return Err(format!("Unsupported gpg.format '{}'", other)),
+        };
+        let (format_key, default_program) = match format {
+            SigningFormat::OpenPgp => ("gpg.openpgp.program", "gpg"),
+            SigningFormat::X509 => ("gpg.x509.program", "gpgsm"),
+            SigningFormat::Ssh => ("gpg.ssh.program", "ssh-keygen"),
+        };
+        let program = config
+            .get_string(format_key)
+            .or_else(|e| match format {
+                // The legacy `gpg.program` only applies to OpenPGP
+                SigningFormat::OpenPgp => config.get_string("gpg.program"),
+                _ => Err(e),
+            })
+            .unwrap_or_else(|_| default_program.to_string());
+        let key = config.get_string("user.signingkey").ok().filter(|k| !k.is_empty());
+        Ok(SigningConfig { format, program, key })
+    }
+
+    fn format_name(&self) -> &'static str {
+        match self.format {
+            SigningFormat::OpenPgp => "openpgp",
+            SigningFormat::X509 => "x509",
+            SigningFormat::Ssh => "ssh",
+        }
+    }
+}
+
+// A literal SSH public key in user.signingkey is written to a temporary file for ssh-keygen
+struct TempKeyFile(PathBuf);
+
+impl Drop for TempKeyFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.0);
+    }
+}
+
+// Expands a leading `~/` the way git does for paths in its config
+pub(crate) fn expand_home(path: &str) -> String {
+    match (path.strip_prefix("~/"), std::env::var("HOME")) {
+        (Some(rest), Ok(home)) => format!("{}/{}", home, rest),
+        _ => path.to_string(),
+    }
+}
+
+fn run_signer(config: &SigningConfig, args: &[String], payload: &str) -> Result<String, String> {
+    let mut child = Command::new(&config.program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| match e.kind() {
+            io::ErrorKind::NotFound => format!(
+                "Signing program '{}' not found (gpg.format = {}). Install it or point gpg.{}program at it.",
+                config.program,
+                config.format_name(),
+                if config.format == SigningFormat::OpenPgp { "" } else { "<format>." }
+            ),
+            _ => format!("Failed to run '{}': {}", config.program, e),
+        })?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(payload.as_bytes()).map_err(|e| e.to_string())?;
+    }
+    let output = child.wait_with_output().map_err(|e| e.to_string())?;
+    let signature = String::from_utf8_lossy(&output.stdout).to_string();
+    if !output.status.success() || signature.trim().is_empty() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!("'{}' failed to sign the commit: {}", config.program, stderr.trim()));
+    }
+    Ok(signature)
+}
+
+fn sign_payload(config: &SigningConfig, payload: &str, committer: &Signature) -> Result<String, String> {
+    match config.format {
+        SigningFormat::OpenPgp | SigningFormat::X509 => {
+            // Without a configured key, gpg picks one matching the committer like the CLI does
+            let key = config.key.clone().unwrap_or_else(|| {
+                format!(
+                    "{} <{}>",
+                    committer.name().unwrap_or(""),
+                    committer.email().unwrap_or("")
+                )
+            });
+            let args = vec!["--status-fd=2".to_string(), "-bsau".to_string(), key];
+            run_signer(config, &args, payload)
+        }
+        SigningFormat::Ssh => {
+            let key = config
+                .key
+                .as_deref()
+                .ok_or("SSH signing needs user.signingkey to be set")?;
+            let literal = key.strip_prefix("key::").unwrap_or(key);
+            let mut args: Vec<String> = vec!["-Y".into(), "sign".into(), "-n".into(), "git".into()];
+
+            let _temp;
+            if literal.starts_with("ssh-") || literal.starts_with("ecdsa-") || literal.starts_with("sk-") {
+                let nanos = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map(|d| d.as_nanos())
+                    .unwrap_or(0);
+                let path = std::env::temp_dir()
+                    .join(format!(".maker_signing_key_{}_{}.pub", std::process::id(), nanos));
+                fs::write(&path, literal).map_err(|e| e.to_string())?;
+                // The private half of a literal public key has to come from ssh-agent
+                args.extend(["-U".to_string(), "-f".to_string(), path.to_string_lossy().to_string()]);
+                _temp = TempKeyFile(path);
+            } else {
+                args.extend(["-f".to_string(), expand_home(key)]);
+            }
+            run_signer(config, &args, payload)
+        }
+    }
+}
+
+// Writes a commit object without moving any reference. When `sign` is None,
+// `commit.gpgsign` from git config decides whether the commit is signed.
+pub(crate) fn create_commit(
+    repo: &Repository,
+    author: &Signature,
+    committer: &Signature,
+    message: &str,
+    tree: &Tree,
+    parents: &[&Commit],
+    sign: Option<bool>,
+) -> Result<Oid, String> {
+    let config = repo.config().map_err(|e| e.message().to_string())?;
+    let sign = sign.unwrap_or_else(|| config.get_bool("commit.gpgsign").unwrap_or(false));
+    if !sign {
+        return repo
+            .commit(None, author, committer, message, tree, parents)
+            .map_err(|e| e.message().to_string());
+    }
+
+    let signing = SigningConfig::from_config(&config)?;
+    let buffer = repo
+        .commit_create_buffer(author, committer, message, tree, parents)
+        .map_err(|e| e.message().to_string())?;
+    let payload = buffer.as_str().ok_or("Commit buffer is not valid UTF-8")?;
+    let signature = sign_payload(&signing, payload, committer)?;
+    repo.commit_signed(payload, &signature, None)
+        .map_err(|e| e.message().to_string())
+}
+
+// Points HEAD at `oid`: the branch HEAD refers to (even when unborn) is moved, a detached
+// HEAD is re-pointed. `expected` is where HEAD has to still be (None: the branch must not
+// exist yet), so a commit written concurrently by another step is never orphaned.
+pub(crate) fn update_head(
+    repo: &Repository,
+    oid: Oid,
+    expected: Option<Oid>,
+    reflog: &str,
+) -> Result<(), String> {
+    let head = repo.find_reference("HEAD").map_err(|e| e.message().to_string())?;
+    let target = head.symbolic_target().unwrap_or("HEAD").to_string();
+    repo.reference_matching(&target, oid, true, expected.unwrap_or_else(Oid::zero), reflog)
+        .map(|_| ())
+        .map_err(|e| match e.code() {
+            ErrorCode::Modified => format!("{} moved while the commit was being written; try again", target),
+            _ => e.message().to_string(),
+        })
+}
+
+// Creates a commit (see `create_commit`) and advances HEAD to it, like `repo.commit(Some("HEAD"), ..)`
+pub(crate) fn write_commit(
+    repo: &Repository,
+    author: &Signature,
+    committer: &Signature,
+    message: &str,
+    tree: &Tree,
+    parents: &[&Commit],
+    sign: Option<bool>,
+) -> Result<Oid, String> {
+    let oid = create_commit(repo, author, committer, message, tree, parents, sign)?;
+    let summary = message.lines().next().unwrap_or("");
+    let reflog = if parents.is_empty() {
+        format!("commit (initial): {}", summary)
+    } else {
+        format!("commit: {}", summary)
+    };
+    update_head(repo, oid, parents.first().map(|parent| parent.id()), &reflog)?;
+    Ok(oid)
+}
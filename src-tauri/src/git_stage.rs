@@ -0,0 +1,262 @@
+// Selective staging on top of the git2 index, so a checkpoint can commit only the
+// files (or hunks) a step actually touched. Paths are relative to the repository root.
+
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::path::{Component, Path};
+
+use git2::{
+    ApplyLocation, ApplyOptions, Delta, DiffOptions, Index, IndexAddOption, IndexEntry, IndexTime, ObjectType,
+    Oid, Patch, Repository, TreeWalkMode, TreeWalkResult,
+};
+
+// Whether `entry` is one of `paths` or lies below one of them, compared literally
+fn is_requested(entry: &Path, paths: &[String]) -> bool {
+    paths
+        .iter()
+        .any(|p| p == "." || entry.starts_with(p.trim_end_matches('/')))
+}
+
+// Blob and mode of every index entry by path, to count the entries an operation changed
+fn index_snapshot(index: &Index) -> HashMap<Vec<u8>, (Oid, u32)> {
+    index.iter().map(|entry| (entry.path, (entry.id, entry.mode))).collect()
+}
+
+fn changed_entries(before: &HashMap<Vec<u8>, (Oid, u32)>, after: &HashMap<Vec<u8>, (Oid, u32)>) -> usize {
+    let removed = before.keys().filter(|path| !after.contains_key(*path)).count();
+    removed + after.iter().filter(|(path, entry)| before.get(*path) != Some(entry)).count()
+}
+
+// Stages the given files or directories. Ignored paths that are not tracked yet are skipped
+// and listed in the result.
+#[tauri::command]
+pub fn git_stage(path: String, paths: Vec<String>) -> Result<String, String> {
+    if paths.is_empty() {
+        return Ok("Nothing to stage".to_string());
+    }
+    let repo = Repository::open(&path).map_err(|e| e.message().to_string())?;
+    let mut index = repo.index().map_err(|e| e.message().to_string())?;
+    let before = index_snapshot(&index);
+
+    let ignored: Vec<&str> = paths
+        .iter()
+        .map(String::as_str)
+        .filter(|p| {
+            let p = Path::new(p);
+            // git2 panics on `./` or `../` in index paths; such paths never match anything anyway
+            p.components().all(|c| matches!(c, Component::Normal(_)))
+                && index.get_path(p, 0).is_none()
+                && repo.status_should_ignore(p).unwrap_or(false)
+        })
+        .collect();
+
+    // Pathspecs are globs to libgit2, so `app/[id]/page.tsx` would also match
+    // `app/i/page.tsx`. Only literal matches get staged.
+    let mut literal_only = |entry: &Path, _: &[u8]| if is_requested(entry, &paths) { 0 } else { 1 };
+    // add_all picks up new and modified files, update_all drops entries deleted on disk
+    index
+        .add_all(paths.iter(), IndexAddOption::DISABLE_PATHSPEC_MATCH, Some(&mut literal_only))
+        .map_err(|e| e.message().to_string())?;
+    index
+        .update_all(paths.iter(), Some(&mut literal_only))
+        .map_err(|e| e.message().to_string())?;
+
+    index.write().map_err(|e| e.message().to_string())?;
+    let staged = changed_entries(&before, &index_snapshot(&index));
+    if ignored.is_empty() {
+        Ok(format!("Staged {} path(s)", staged))
+    } else {
+        Ok(format!("Staged {} path(s); skipped ignored: {}", staged, ignored.join(", ")))
+    }
+}
+
+// Resets the index entries of the given files or directories to what HEAD has, dropping
+// the ones HEAD lacks. Paths are matched literally, as in `git_stage`.
+#[tauri::command]
+pub fn git_unstage(path: String, paths: Vec<String>) -> Result<String, String> {
+    if paths.is_empty() {
+        return Ok("Nothing to unstage".to_string());
+    }
+    let repo = Repository::open(&path).map_err(|e| e.message().to_string())?;
+    let mut index = repo.index().map_err(|e| e.message().to_string())?;
+    let before = index_snapshot(&index);
+
+    // Unborn HEAD: nothing has been committed yet, so unstaging means dropping the entries
+    let mut committed: HashMap<String, (Oid, u32)> = HashMap::new();
+    if let Some(head) = repo.head().ok().and_then(|head| head.peel_to_commit().ok()) {
+        let tree = head.tree().map_err(|e| e.message().to_string())?;
+        tree.walk(TreeWalkMode::PreOrder, |root, entry| {
+            if entry.kind() != Some(ObjectType::Tree) {
+                let file = format!("{}{}", root, String::from_utf8_lossy(entry.name_bytes()));
+                if is_requested(Path::new(&file), &paths) {
+                    committed.insert(file, (entry.id(), entry.filemode() as u32));
+                }
+            }
+            TreeWalkResult::Ok
+        })
+        .map_err(|e| e.message().to_string())?;
+    }
+
+    let staged: Vec<String> = index
+        .iter()
+        .map(|entry| String::from_utf8_lossy(&entry.path).to_string())
+        .filter(|file| is_requested(Path::new(file), &paths))
+        .collect();
+    for file in staged {
+        // Entries that already match HEAD keep their stat data
+        if committed.get(&file) != before.get(file.as_bytes()) {
+            index.remove_path(Path::new(&file)).map_err(|e| e.message().to_string())?;
+        }
+    }
+    for (file, (id, mode)) in committed {
+        if index.get_path(Path::new(&file), 0).is_some() {
+            continue;
+        }
+        // Without stat data the next status compares the file by content
+        let entry = IndexEntry {
+            ctime: IndexTime::new(0, 0),
+            mtime: IndexTime::new(0, 0),
+            dev: 0,
+            ino: 0,
+            mode,
+            uid: 0,
+            gid: 0,
+            file_size: 0,
+            id,
+            flags: 0,
+            flags_extended: 0,
+            path: file.into_bytes(),
+        };
+        index.add(&entry).map_err(|e| e.message().to_string())?;
+    }
+    index.write().map_err(|e| e.message().to_string())?;
+    let unstaged = changed_entries(&before, &index_snapshot(&index));
+    Ok(format!("Unstaged {} path(s)", unstaged))
+}
+
+// Stages individual hunks of one file. `hunk_ids` are 0-based positions of the hunks in
+// the file's unstaged (index to working tree) diff with the default 3 lines of context.
+#[tauri::command]
+pub fn git_stage_hunks(path: String, file: String, hunk_ids: Vec<usize>) -> Result<String, String> {
+    if hunk_ids.is_empty() {
+        return Ok("Nothing to stage".to_string());
+    }
+    let repo = Repository::open(&path).map_err(|e| e.message().to_string())?;
+
+    let mut diff_opts = DiffOptions::new();
+    diff_opts
+        .pathspec(&file)
+        .disable_pathspec_match(true)
+        .include_untracked(true)
+        .show_untracked_content(true);
+    let diff = repo
+        .diff_index_to_workdir(None, Some(&mut diff_opts))
+        .map_err(|e| e.message().to_string())?;
+    if diff.deltas().len() == 0 {
+        return Err(format!("No unstaged changes in {}", file));
+    }
+    let hunk_count = Patch::from_diff(&diff, 0)
+        .map_err(|e| e.message().to_string())?
+        .map_or(0, |patch| patch.num_hunks());
+    // Validate up front so a bad id never leaves the index half-applied
+    if let Some(unknown) = hunk_ids.iter().find(|id| **id >= hunk_count) {
+        return Err(format!("Hunk {} does not exist in {} ({} hunk(s))", unknown, file, hunk_count));
+    }
+
+    // An untracked file is a single all-new hunk, and apply cannot create index entries
+    if diff.deltas().any(|delta| delta.status() == Delta::Untracked) {
+        let mut index = repo.index().map_err(|e| e.message().to_string())?;
+        index
+            .add_path(Path::new(&file))
+            .map_err(|e| e.message().to_string())?;
+        index.write().map_err(|e| e.message().to_string())?;
+        return Ok(format!("Staged 1 hunk(s) of {}", file));
+    }
+
+    let position = Cell::new(0usize);
+    let staged = Cell::new(0usize);
+    let mut apply_opts = ApplyOptions::new();
+    apply_opts.hunk_callback(|hunk| {
+        if hunk.is_none() {
+            return false;
+        }
+        let id = position.get();
+        position.set(id + 1);
+        let selected = hunk_ids.contains(&id);
+        if selected {
+            staged.set(staged.get() + 1);
+        }
+        selected
+    });
+    repo.apply(&diff, ApplyLocation::Index, Some(&mut apply_opts))
+        .map_err(|e| e.message().to_string())?;
+    Ok(format!("Staged {} hunk(s) of {}", staged.get(), file))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::test_support::{commit_file, init_repo, path_str, stage_file, TempDir};
+
+    const BRACKETED: &str = "app/[id]/page.tsx";
+    const PLAIN: &str = "app/i/page.tsx";
+
+    fn staged_blob(repo: &Repository, file: &str) -> Option<Oid> {
+        let repo = Repository::open(repo.path()).unwrap();
+        let index = repo.index().unwrap();
+        index.get_path(Path::new(file), 0).map(|entry| entry.id)
+    }
+
+    fn committed_blob(repo: &Repository, file: &str) -> Oid {
+        let tree = repo.head().unwrap().peel_to_tree().unwrap();
+        tree.get_path(Path::new(file)).unwrap().id()
+    }
+
+    #[test]
+    fn stage_counts_only_changed_entries() {
+        let dir = TempDir::new("stage-count");
+        let repo = init_repo(&dir.join("repo"));
+        commit_file(&repo, "README.md", "hello\n", "Initial commit");
+        fs::write(dir.join("repo").join("a.txt"), "a\n").unwrap();
+
+        let paths = ["a.txt", "README.md", "missing.txt", "./a.txt"].map(String::from).to_vec();
+        let result = git_stage(path_str(repo.workdir().unwrap()), paths).unwrap();
+
+        assert_eq!(result, "Staged 1 path(s)");
+        assert!(staged_blob(&repo, "a.txt").is_some());
+    }
+
+    #[test]
+    fn unstage_matches_paths_literally() {
+        let dir = TempDir::new("unstage");
+        let repo = init_repo(&dir.join("repo"));
+        stage_file(&repo, BRACKETED, "one\n");
+        commit_file(&repo, PLAIN, "one\n", "Pages");
+        stage_file(&repo, BRACKETED, "two\n");
+        stage_file(&repo, PLAIN, "two\n");
+        stage_file(&repo, "app/[id]/new.tsx", "new\n");
+
+        let result = git_unstage(path_str(repo.workdir().unwrap()), vec!["app/[id]".to_string()]).unwrap();
+
+        assert_eq!(result, "Unstaged 2 path(s)");
+        assert_eq!(staged_blob(&repo, BRACKETED), Some(committed_blob(&repo, BRACKETED)));
+        assert_eq!(staged_blob(&repo, "app/[id]/new.tsx"), None);
+        assert_ne!(staged_blob(&repo, PLAIN), Some(committed_blob(&repo, PLAIN)));
+    }
+
+    #[test]
+    fn unstage_on_unborn_head_drops_only_literal_matches() {
+        let dir = TempDir::new("unstage-unborn");
+        let repo = init_repo(&dir.join("repo"));
+        stage_file(&repo, BRACKETED, "one\n");
+        stage_file(&repo, PLAIN, "one\n");
+
+        let result = git_unstage(path_str(repo.workdir().unwrap()), vec![BRACKETED.to_string()]).unwrap();
+
+        assert_eq!(result, "Unstaged 1 path(s)");
+        assert_eq!(staged_blob(&repo, BRACKETED), None);
+        assert!(staged_blob(&repo, PLAIN).is_some());
+    }
+}
@@ -0,0 +1,113 @@
+// Stashes, so the engine can set a user's uncommitted work aside before running a task
+// and restore it afterwards instead of checkpointing it into history.
+
+use git2::{ErrorCode, Oid, Repository, StashApplyOptions, StashFlags};
+use serde::Serialize;
+
+use super::{commit_signature, get_git_status, GitStatus};
+
+#[derive(Serialize)]
+pub(crate) struct StashEntry {
+    // Position in the stash list; 0 is the most recent (stash@{0})
+    index: usize,
+    message: String,
+    oid: String,
+    // Seconds since the Unix epoch
+    time: i64,
+}
+
+fn stash_entries(repo: &mut Repository) -> Result<Vec<(usize, String, Oid)>, String> {
+    let mut entries = Vec::new();
+    repo.stash_foreach(|index, message, oid| {
+        entries.push((index, message.to_string(), *oid));
+        true
+    })
+    .map_err(|e| e.message().to_string())?;
+    Ok(entries)
+}
+
+fn check_index(repo: &mut Repository, index: usize) -> Result<(), String> {
+    let count = stash_entries(repo)?.len();
+    if index >= count {
+        return Err(format!("No stash entry stash@{{{}}} ({} stashed)", index, count));
+    }
+    Ok(())
+}
+
+// Stashes staged and unstaged changes, plus untracked files with `include_untracked`.
+// Returns the stash commit, or None when there was nothing to stash.
+#[tauri::command]
+pub fn git_stash_save(
+    path: String,
+    message: Option<String>,
+    include_untracked: Option<bool>,
+) -> Result<Option<String>, String> {
+    let mut repo = Repository::open(&path).map_err(|e| e.message().to_string())?;
+    let stasher = commit_signature(&repo)?;
+    let mut flags = StashFlags::DEFAULT;
+    if include_untracked.unwrap_or(false) {
+        flags |= StashFlags::INCLUDE_UNTRACKED;
+    }
+    match repo.stash_save2(&stasher, message.as_deref(), Some(flags)) {
+        Ok(oid) => Ok(Some(oid.to_string())),
+        Err(e) if e.code() == ErrorCode::NotFound => Ok(None),
+        Err(e) => Err(e.message().to_string()),
+    }
+}
+
+// Most recent first
+#[tauri::command]
+pub fn git_stash_list(path: String) -> Result<Vec<StashEntry>, String> {
+    let mut repo = Repository::open(&path).map_err(|e| e.message().to_string())?;
+    let entries = stash_entries(&mut repo)?;
+    entries
+        .into_iter()
+        .map(|(index, message, oid)| {
+            let commit = repo.find_commit(oid).map_err(|e| e.message().to_string())?;
+            Ok(StashEntry {
+                index,
+                message,
+                oid: oid.to_string(),
+                time: commit.time().seconds(),
+            })
+        })
+        .collect()
+}
+
+// Applies stash@{index} (default 0) onto the working tree, restoring what was staged as
+// staged. With `pop` the entry is dropped if it applied cleanly. Refuses when it would
+// overwrite local changes; conflicting hunks show up as conflicted files in the returned
+// status and the entry is kept.
+#[tauri::command]
+pub fn git_stash_apply(path: String, index: Option<usize>, pop: Option<bool>) -> Result<GitStatus, String> {
+    let mut repo = Repository::open(&path).map_err(|e| e.message().to_string())?;
+    let index = index.unwrap_or(0);
+    check_index(&mut repo, index)?;
+
+    let mut opts = StashApplyOptions::new();
+    opts.reinstantiate_index();
+    repo.stash_apply(index, Some(&mut opts)).map_err(|e| {
+        if e.code() == ErrorCode::Conflict {
+            format!("Cannot apply stash@{{{}}}: {}", index, e.message())
+        } else {
+            e.message().to_string()
+        }
+    })?;
+    // libgit2's own pop drops the entry even when the apply left conflicts behind
+    let conflicted = repo
+        .index()
+        .map(|idx| idx.has_conflicts())
+        .map_err(|e| e.message().to_string())?;
+    if pop.unwrap_or(false) && !conflicted {
+        repo.stash_drop(index).map_err(|e| e.message().to_string())?;
+    }
+    get_git_status(path, None)
+}
+
+#[tauri::command]
+pub fn git_stash_drop(path: String, index: Option<usize>) -> Result<(), String> {
+    let mut repo = Repository::open(&path).map_err(|e| e.message().to_string())?;
+    let index = index.unwrap_or(0);
+    check_index(&mut repo, index)?;
+    repo.stash_drop(index).map_err(|e| e.message().to_string())
+}
@@ -0,0 +1,599 @@
+// Per-step worktrees under `.maker/worktrees/<stepId>`, each on its own
+// `maker/<taskId>/step-<stepId>` branch, managed through git2 instead of the git CLI.
+// `maker_gc` cleans up what crashed or forgotten runs leave behind.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use git2::{
+    BranchType, Commit, Delta, Repository, StatusOptions, Worktree, WorktreeAddOptions, WorktreeLockStatus,
+    WorktreePruneOptions,
+};
+use serde::Serialize;
+
+use super::resolve_commit;
+
+const WORKTREES_DIR: &str = ".maker/worktrees";
+// `maker_gc` leaves anything touched within the last week alone unless told otherwise
+const DEFAULT_GC_AGE_SECS: u64 = 7 * 24 * 60 * 60;
+
+#[derive(Serialize)]
+pub(crate) struct WorktreeInfo {
+    // Worktree name as known to git; None for the main working tree
+    name: Option<String>,
+    path: String,
+    branch: Option<String>,
+    head: Option<String>,
+    is_main: bool,
+    is_locked: bool,
+    lock_reason: Option<String>,
+    is_dirty: bool,
+    // The working directory is gone, so `worktree_prune` would remove it
+    is_prunable: bool,
+}
+
+// Opens the main repository, even when `path` points inside a linked worktree
+pub(crate) fn open_main_repo(path: &str) -> Result<Repository, String> {
+    let repo = Repository::open(path).map_err(|e| e.message().to_string())?;
+    if repo.is_worktree() {
+        // A linked worktree's git dir records the shared one in its `commondir` file
+        let gitdir = repo.path();
+        let common = fs::read_to_string(gitdir.join("commondir")).map_err(|e| e.to_string())?;
+        return Repository::open(gitdir.join(common.trim())).map_err(|e| e.message().to_string());
+    }
+    Ok(repo)
+}
+
+// Step ids name the worktree and its directory under .maker/worktrees, so they must be a
+// single plain path component: no separators, `.` or `..`
+fn validate_step_id(step_id: &str) -> Result<(), String> {
+    let mut components = Path::new(step_id).components();
+    let single = matches!((components.next(), components.next()), (Some(Component::Normal(_)), None));
+    if single && !step_id.contains(['/', '\\']) {
+        Ok(())
+    } else {
+        Err(format!("Invalid step id '{}': it must be a single path component", step_id))
+    }
+}
+
+fn step_branch_name(task_id: &str, step_id: &str) -> String {
+    format!("maker/{}/step-{}", task_id, step_id)
+}
+
+fn is_dirty(repo: &Repository) -> bool {
+    let mut opts = StatusOptions::new();
+    opts.include_untracked(true);
+    repo.statuses(Some(&mut opts))
+        .map(|statuses| !statuses.is_empty())
+        .unwrap_or(false)
+}
+
+fn describe_head(repo: &Repository) -> (Option<String>, Option<String>) {
+    match repo.head() {
+        Ok(head) => (
+            head.is_branch().then(|| head.shorthand().map(str::to_string)).flatten(),
+            head.target().map(|oid| oid.to_string()),
+        ),
+        Err(_) => (None, None),
+    }
+}
+
+fn main_info(repo: &Repository) -> WorktreeInfo {
+    let (branch, head) = describe_head(repo);
+    let path = repo.workdir().unwrap_or_else(|| repo.path());
+    WorktreeInfo {
+        name: None,
+        path: path.to_string_lossy().to_string(),
+        branch,
+        head,
+        is_main: true,
+        is_locked: false,
+        lock_reason: None,
+        is_dirty: is_dirty(repo),
+        is_prunable: false,
+    }
+}
+
+fn worktree_info(worktree: &Worktree) -> WorktreeInfo {
+    let (is_locked, lock_reason) = match worktree.is_locked() {
+        Ok(WorktreeLockStatus::Locked(reason)) => (true, reason),
+        _ => (false, None),
+    };
+    let is_prunable = worktree.validate().is_err();
+    let opened = (!is_prunable)
+        .then(|| Repository::open_from_worktree(worktree).ok())
+        .flatten();
+    let (branch, head) = opened.as_ref().map(describe_head).unwrap_or((None, None));
+
+    WorktreeInfo {
+        name: worktree.name().map(str::to_string),
+        path: worktree.path().to_string_lossy().to_string(),
+        branch,
+        head,
+        is_main: false,
+        is_locked,
+        lock_reason,
+        is_dirty: opened.as_ref().is_some_and(is_dirty),
+        is_prunable,
+    }
+}
+
+#[derive(Serialize)]
+pub(crate) struct GcBranch {
+    name: String,
+    // "merged" when HEAD already contains its changes (also after a squash merge), else "abandoned"
+    reason: &'static str,
+    // Seconds since the Unix epoch
+    last_activity: i64,
+    // Name of the worktree removed along with the branch
+    worktree: Option<String>,
+}
+
+#[derive(Serialize)]
+pub(crate) struct GcReport {
+    dry_run: bool,
+    branches: Vec<GcBranch>,
+    // Worktree entries whose working directory no longer exists
+    stale_worktrees: Vec<String>,
+    // Directories under .maker/worktrees that no worktree is registered at
+    orphaned_dirs: Vec<String>,
+    // Candidates that were left alone, with the reason
+    skipped: Vec<String>,
+}
+
+fn prune_options(force: bool) -> WorktreePruneOptions {
+    let mut opts = WorktreePruneOptions::new();
+    // `valid` allows pruning a worktree whose directory still exists; `working_tree` deletes it
+    opts.valid(true).working_tree(true).locked(force);
+    opts
+}
+
+// Checks out `maker/<taskId>/step-<stepId>` (created from `base`, default HEAD, if missing)
+// into `.maker/worktrees/<stepId>`. A stale worktree left behind under the same name is replaced.
+#[tauri::command]
+pub fn worktree_create(
+    path: String,
+    task_id: String,
+    step_id: String,
+    base: Option<String>,
+) -> Result<WorktreeInfo, String> {
+    validate_step_id(&step_id)?;
+    let repo = open_main_repo(&path)?;
+    let root = repo
+        .workdir()
+        .ok_or("Repository has no working directory")?
+        .to_path_buf();
+
+    let branch_name = step_branch_name(&task_id, &step_id);
+    let branch = match repo.find_branch(&branch_name, BranchType::Local) {
+        Ok(branch) => branch,
+        Err(_) => {
+            let spec = base.as_deref().unwrap_or("HEAD");
+            let commit = resolve_commit(&repo, spec)?;
+            repo.branch(&branch_name, &commit, false)
+                .map_err(|e| e.message().to_string())?
+        }
+    };
+
+    if let Ok(existing) = repo.find_worktree(&step_id) {
+        if existing.validate().is_ok() {
+            return Err(format!("Worktree '{}' already exists at {}", step_id, existing.path().display()));
+        }
+        existing
+            .prune(Some(&mut prune_options(false)))
+            .map_err(|e| e.message().to_string())?;
+    }
+
+    let worktree_path: PathBuf = root.join(WORKTREES_DIR).join(&step_id);
+    if let Some(parent) = worktree_path.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+
+    let reference = branch.into_reference();
+    let mut opts = WorktreeAddOptions::new();
+    opts.reference(Some(&reference));
+    let worktree = repo
+        .worktree(&step_id, &worktree_path, Some(&opts))
+        .map_err(|e| e.message().to_string())?;
+    Ok(worktree_info(&worktree))
+}
+
+// The main working tree first, followed by every linked worktree
+#[tauri::command]
+pub fn worktree_list(path: String) -> Result<Vec<WorktreeInfo>, String> {
+    let repo = open_main_repo(&path)?;
+    let mut result = vec![main_info(&repo)];
+
+    let names = repo.worktrees().map_err(|e| e.message().to_string())?;
+    for name in names.iter().flatten() {
+        match repo.find_worktree(name) {
+            Ok(worktree) => result.push(worktree_info(&worktree)),
+            Err(e) => log::warn!("Skipping worktree '{}': {}", name, e.message()),
+        }
+    }
+    Ok(result)
+}
+
+// Deletes the worktree directory and its administrative files. Dirty or locked worktrees
+// are refused unless `force` is set; `delete_branch` also removes the branch it had checked out.
+#[tauri::command]
+pub fn worktree_remove(
+    path: String,
+    name: String,
+    force: Option<bool>,
+    delete_branch: Option<bool>,
+) -> Result<String, String> {
+    let repo = open_main_repo(&path)?;
+    let worktree = repo.find_worktree(&name).map_err(|e| e.message().to_string())?;
+    let info = worktree_info(&worktree);
+    let force = force.unwrap_or(false);
+
+    if !force {
+        if info.is_locked {
+            return Err(format!("Worktree '{}' is locked", name));
+        }
+        if info.is_dirty {
+            return Err(format!("Worktree '{}' has uncommitted changes", name));
+        }
+    }
+
+    worktree
+        .prune(Some(&mut prune_options(force)))
+        .map_err(|e| e.message().to_string())?;
+    // libgit2 leaves the directory behind when the worktree was already invalid
+    let dir = Path::new(&info.path);
+    if dir.exists() {
+        fs::remove_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+
+    if delete_branch.unwrap_or(false) {
+        if let Some(branch_name) = &info.branch {
+            let mut branch = repo
+                .find_branch(branch_name, BranchType::Local)
+                .map_err(|e| e.message().to_string())?;
+            branch.delete().map_err(|e| e.message().to_string())?;
+        }
+    }
+    Ok(format!("Removed worktree {}", name))
+}
+
+// Drops the bookkeeping of worktrees whose directories no longer exist. Locked ones are kept.
+// Returns the names that were pruned.
+#[tauri::command]
+pub fn worktree_prune(path: String) -> Result<Vec<String>, String> {
+    let repo = open_main_repo(&path)?;
+    let names = repo.worktrees().map_err(|e| e.message().to_string())?;
+
+    let mut pruned = Vec::new();
+    for name in names.iter().flatten() {
+        let Ok(worktree) = repo.find_worktree(name) else { continue };
+        // Default options only consider invalid, unlocked worktrees prunable
+        if worktree.is_prunable(None).unwrap_or(false) {
+            worktree.prune(None).map_err(|e| e.message().to_string())?;
+            pruned.push(name.to_string());
+        }
+    }
+    Ok(pruned)
+}
+
+fn modified_secs(path: &Path) -> Option<i64> {
+    let modified = fs::metadata(path).and_then(|meta| meta.modified()).ok()?;
+    modified.duration_since(UNIX_EPOCH).ok().map(|d| d.as_secs() as i64)
+}
+
+fn canonical(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+// Newest of the branch tip's commit time, the branch's reflog and its worktree's files.
+// A freshly created step branch still points at an old commit, but its reflog is new.
+fn last_activity(repo: &Repository, refname: &str, tip: &Commit, worktree: Option<&Worktree>) -> i64 {
+    let mut latest = tip.time().seconds();
+    if let Some(entry) = repo.reflog(refname).ok().as_ref().and_then(|reflog| reflog.get(0)) {
+        latest = latest.max(entry.committer().when().seconds());
+    }
+    if let Some(worktree) = worktree {
+        let admin_dir = repo.path().join("worktrees").join(worktree.name().unwrap_or(""));
+        for path in [worktree.path(), &admin_dir.join("index")] {
+            latest = latest.max(modified_secs(path).unwrap_or(0));
+        }
+    }
+    latest
+}
+
+// Whether HEAD already has everything on `tip`: it is an ancestor, or every file the branch
+// changed since the merge base is in HEAD as the branch left it (the usual outcome of
+// `git_squash_merge`). Only reads objects, so a dry run leaves the object store alone.
+fn is_merged(repo: &Repository, head: &Commit, tip: &Commit) -> bool {
+    if head.id() == tip.id() || repo.graph_descendant_of(head.id(), tip.id()).unwrap_or(false) {
+        return true;
+    }
+    let trees = repo
+        .merge_base(head.id(), tip.id())
+        .and_then(|base| repo.find_commit(base)?.tree())
+        .and_then(|base| Ok((base, tip.tree()?, head.tree()?)));
+    let Ok((base_tree, tip_tree, head_tree)) = trees else { return false };
+    let Ok(diff) = repo.diff_tree_to_tree(Some(&base_tree), Some(&tip_tree), None) else {
+        return false;
+    };
+    diff.deltas().all(|delta| {
+        let Some(path) = delta.new_file().path().or(delta.old_file().path()) else { return false };
+        match head_tree.get_path(path) {
+            Ok(entry) => delta.status() != Delta::Deleted && entry.id() == delta.new_file().id(),
+            Err(_) => delta.status() == Delta::Deleted,
+        }
+    })
+}
+
+fn gc_blocking(path: String, older_than: Option<u64>, dry_run: Option<bool>) -> Result<GcReport, String> {
+    let repo = open_main_repo(&path)?;
+    let root = repo
+        .workdir()
+        .ok_or("Repository has no working directory")?
+        .to_path_buf();
+    let dry_run = dry_run.unwrap_or(true);
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_err(|e| e.to_string())?
+        .as_secs() as i64;
+    let cutoff = now - older_than.unwrap_or(DEFAULT_GC_AGE_SECS) as i64;
+
+    let mut report = GcReport {
+        dry_run,
+        branches: Vec::new(),
+        stale_worktrees: Vec::new(),
+        orphaned_dirs: Vec::new(),
+        skipped: Vec::new(),
+    };
+
+    // Stale entries go first, so they no longer count as having their branch checked out
+    let mut by_branch: HashMap<String, (Worktree, WorktreeInfo)> = HashMap::new();
+    let mut registered: Vec<PathBuf> = Vec::new();
+    let names = repo.worktrees().map_err(|e| e.message().to_string())?;
+    for name in names.iter().flatten() {
+        let Ok(worktree) = repo.find_worktree(name) else { continue };
+        if worktree.validate().is_ok() {
+            let info = worktree_info(&worktree);
+            registered.push(canonical(worktree.path()));
+            if let Some(branch) = info.branch.clone() {
+                by_branch.insert(branch, (worktree, info));
+            }
+        } else if worktree.is_prunable(None).unwrap_or(false) {
+            if !dry_run {
+                worktree.prune(None).map_err(|e| e.message().to_string())?;
+            }
+            report.stale_worktrees.push(name.to_string());
+        } else {
+            report.skipped.push(format!("worktree {}: locked", name));
+        }
+    }
+
+    let head = repo.head().ok();
+    let head_branch = head
+        .as_ref()
+        .filter(|head| head.is_branch())
+        .and_then(|head| head.shorthand().map(str::to_string));
+    let head_commit = head.and_then(|head| head.peel_to_commit().ok());
+
+    for entry in repo.branches(Some(BranchType::Local)).map_err(|e| e.message().to_string())? {
+        let (branch, _) = entry.map_err(|e| e.message().to_string())?;
+        let Some(name) = branch.name().ok().flatten().map(str::to_string) else { continue };
+        if !name.starts_with("maker/") {
+            continue;
+        }
+        if head_branch.as_deref() == Some(name.as_str()) {
+            report.skipped.push(format!("{}: checked out in the main working tree", name));
+            continue;
+        }
+        let Ok(tip) = branch.get().peel_to_commit() else { continue };
+        let worktree = by_branch.get(&name);
+        let refname = branch.get().name().unwrap_or("");
+        let activity = last_activity(&repo, refname, &tip, worktree.map(|(worktree, _)| worktree));
+        if activity > cutoff {
+            continue;
+        }
+        if let Some((_, info)) = worktree {
+            if info.is_locked {
+                report.skipped.push(format!("{}: worktree {} is locked", name, info.path));
+                continue;
+            }
+            if info.is_dirty {
+                report
+                    .skipped
+                    .push(format!("{}: worktree {} has uncommitted changes", name, info.path));
+                continue;
+            }
+        }
+        let merged = head_commit.as_ref().is_some_and(|head| is_merged(&repo, head, &tip));
+        report.branches.push(GcBranch {
+            name,
+            reason: if merged { "merged" } else { "abandoned" },
+            last_activity: activity,
+            worktree: worktree.and_then(|(_, info)| info.name.clone()),
+        });
+    }
+
+    if !dry_run {
+        for candidate in &report.branches {
+            if let Some((worktree, info)) = by_branch.get(&candidate.name) {
+                worktree
+                    .prune(Some(&mut prune_options(false)))
+                    .map_err(|e| e.message().to_string())?;
+                let dir = Path::new(&info.path);
+                if dir.exists() {
+                    fs::remove_dir_all(dir).map_err(|e| e.to_string())?;
+                }
+            }
+            let mut branch = repo
+                .find_branch(&candidate.name, BranchType::Local)
+                .map_err(|e| e.message().to_string())?;
+            branch.delete().map_err(|e| e.message().to_string())?;
+        }
+    }
+
+    // Directories left by runs that crashed before (or while) registering their worktree
+    if let Ok(entries) = fs::read_dir(root.join(WORKTREES_DIR)) {
+        for entry in entries.flatten() {
+            let dir = entry.path();
+            if !dir.is_dir() || registered.contains(&canonical(&dir)) {
+                continue;
+            }
+            if modified_secs(&dir).unwrap_or(0) > cutoff {
+                continue;
+            }
+            if !dry_run {
+                fs::remove_dir_all(&dir).map_err(|e| e.to_string())?;
+            }
+            report.orphaned_dirs.push(dir.to_string_lossy().to_string());
+        }
+    }
+
+    Ok(report)
+}
+
+// Finds `maker/*` branches with no activity for `older_than` seconds (default a week), stale
+// worktree entries and orphaned directories under .maker/worktrees. Without `dry_run: false`
+// it only reports them; otherwise the branches are deleted together with their worktrees.
+// Branches whose worktree is locked or has uncommitted changes are always kept.
+#[tauri::command]
+pub async fn maker_gc(path: String, older_than: Option<u64>, dry_run: Option<bool>) -> Result<GcReport, String> {
+    tauri::async_runtime::spawn_blocking(move || gc_blocking(path, older_than, dry_run))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::git_merge::git_squash_merge;
+    use crate::test_support::{commit_file, init_repo, path_str, TempDir};
+
+    // A repository with two step worktrees: step 1 without commits of its own (merged),
+    // step 2 with one (abandoned), plus a directory no worktree is registered at
+    fn with_steps(dir: &TempDir) -> (Repository, Vec<PathBuf>) {
+        let repo = init_repo(&dir.join("repo"));
+        commit_file(&repo, "README.md", "hello\n", "Initial commit");
+        let path = path_str(repo.workdir().unwrap());
+
+        let merged = worktree_create(path.clone(), "task".into(), "1".into(), None).unwrap();
+        let abandoned = worktree_create(path, "task".into(), "2".into(), None).unwrap();
+        let step = Repository::open(&abandoned.path).unwrap();
+        commit_file(&step, "step.txt", "work\n", "Step work");
+
+        let orphan = repo.workdir().unwrap().join(WORKTREES_DIR).join("orphan");
+        fs::create_dir_all(&orphan).unwrap();
+        let dirs = vec![PathBuf::from(merged.path), PathBuf::from(abandoned.path), orphan];
+        (repo, dirs)
+    }
+
+    fn has_branch(repo: &Repository, name: &str) -> bool {
+        repo.find_branch(name, BranchType::Local).is_ok()
+    }
+
+    #[test]
+    fn create_rejects_step_ids_outside_the_worktrees_dir() {
+        let dir = TempDir::new("step-id");
+        let repo = init_repo(&dir.join("repo"));
+        commit_file(&repo, "README.md", "hello\n", "Initial commit");
+        let path = path_str(repo.workdir().unwrap());
+
+        for step_id in ["../../x", "..", ".", "a/b", "a\\b", "/tmp/x", ""] {
+            let result = worktree_create(path.clone(), "task".into(), step_id.into(), None);
+            assert!(result.is_err(), "step id {:?} was accepted", step_id);
+        }
+        assert!(repo.worktrees().unwrap().is_empty());
+        assert!(!dir.join("x").exists());
+    }
+
+    #[test]
+    fn gc_dry_run_only_reports() {
+        let dir = TempDir::new("gc-dry");
+        let (repo, dirs) = with_steps(&dir);
+
+        let report = gc_blocking(path_str(repo.workdir().unwrap()), Some(0), None).unwrap();
+
+        assert!(report.dry_run);
+        let mut found: Vec<_> = report
+            .branches
+            .iter()
+            .map(|b| (b.name.as_str(), b.reason, b.worktree.as_deref()))
+            .collect();
+        found.sort();
+        assert_eq!(
+            found,
+            vec![
+                ("maker/task/step-1", "merged", Some("1")),
+                ("maker/task/step-2", "abandoned", Some("2")),
+            ]
+        );
+        assert_eq!(report.orphaned_dirs, vec![path_str(&dirs[2])]);
+        assert!(has_branch(&repo, "maker/task/step-1"));
+        assert!(has_branch(&repo, "maker/task/step-2"));
+        assert!(dirs.iter().all(|dir| dir.exists()));
+        assert_eq!(repo.worktrees().unwrap().len(), 2);
+    }
+
+    fn object_count(repo: &Repository) -> usize {
+        fs::read_dir(repo.path().join("objects"))
+            .unwrap()
+            .flatten()
+            .filter(|dir| dir.path().is_dir())
+            .map(|dir| fs::read_dir(dir.path()).unwrap().count())
+            .sum()
+    }
+
+    #[test]
+    fn gc_dry_run_detects_squash_merges_without_writing_objects() {
+        let dir = TempDir::new("gc-squash");
+        let (repo, _dirs) = with_steps(&dir);
+        let path = path_str(repo.workdir().unwrap());
+        // Merging this one would produce a tree that is not in the repository yet
+        let open = worktree_create(path.clone(), "task".into(), "3".into(), None).unwrap();
+        commit_file(&Repository::open(&open.path).unwrap(), "open.txt", "open\n", "Open work");
+        commit_file(&repo, "main.txt", "main\n", "Main work");
+        git_squash_merge(path, "maker/task/step-2".into(), "Step 2".into(), None, None, None, None).unwrap();
+        let objects = object_count(&repo);
+
+        let report = gc_blocking(path_str(repo.workdir().unwrap()), Some(0), None).unwrap();
+
+        let reason = |name: &str| report.branches.iter().find(|b| b.name == name).map(|b| b.reason);
+        assert_eq!(reason("maker/task/step-2"), Some("merged"));
+        assert_eq!(reason("maker/task/step-3"), Some("abandoned"));
+        assert_eq!(object_count(&repo), objects);
+    }
+
+    #[test]
+    fn gc_deletes_branches_with_their_worktrees() {
+        let dir = TempDir::new("gc-run");
+        let (repo, dirs) = with_steps(&dir);
+
+        let report = gc_blocking(path_str(repo.workdir().unwrap()), Some(0), Some(false)).unwrap();
+
+        assert!(!report.dry_run);
+        assert_eq!(report.branches.len(), 2);
+        assert!(!has_branch(&repo, "maker/task/step-1"));
+        assert!(!has_branch(&repo, "maker/task/step-2"));
+        assert!(dirs.iter().all(|dir| !dir.exists()));
+        assert!(repo.worktrees().unwrap().is_empty());
+        assert!(has_branch(&repo, "main"));
+    }
+
+    #[test]
+    fn gc_keeps_branches_with_dirty_worktrees() {
+        let dir = TempDir::new("gc-dirty");
+        let (repo, dirs) = with_steps(&dir);
+        fs::write(dirs[1].join("unsaved.txt"), "wip\n").unwrap();
+
+        let report = gc_blocking(path_str(repo.workdir().unwrap()), Some(0), Some(false)).unwrap();
+
+        let removed: Vec<_> = report.branches.iter().map(|b| b.name.as_str()).collect();
+        assert_eq!(removed, vec!["maker/task/step-1"]);
+        assert!(report.skipped.iter().any(|s| s.starts_with("maker/task/step-2:")
+            && s.ends_with("has uncommitted changes")));
+        assert!(has_branch(&repo, "maker/task/step-2"));
+        assert!(dirs[1].join("unsaved.txt").exists());
+        assert!(!dirs[0].exists());
+    }
+}
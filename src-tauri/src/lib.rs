@@ -1,15 +1,119 @@
+use std::collections::HashMap;
 use std::fs;
-use std::path::Path;
-use serde::Serialize;
-use git2::{Repository, StatusOptions, BranchType, Signature, IndexAddOption};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::time::UNIX_EPOCH;
+use serde::{Deserialize, Serialize};
+use git2::{Commit, Repository, StatusOptions, BranchType, Signature, IndexAddOption, Status, StatusEntry, Delta};
+use ignore::{WalkBuilder, WalkState};
+use sha2::{Digest, Sha256};
+use tauri::State;
 
-#[derive(Serialize)]
+mod git_auth;
+mod git_branch;
+mod git_diff;
+mod git_history;
+mod git_merge;
+mod git_remote;
+mod git_rewrite;
+mod git_sign;
+mod git_stage;
+mod git_stash;
+mod git_worktree;
+mod project_config;
+mod project_identity;
+mod snapshot;
+#[cfg(test)]
+mod test_support;
+mod tree_watch;
+
+use project_config::ScanMatcher;
+use snapshot::SnapshotStore;
+use tree_watch::TreeWatchers;
+
+#[derive(Serialize, Clone)]
 struct FileNode {
     name: String,
     path: String,
     #[serde(rename = "isDirectory")]
     is_directory: bool,
+    // True when a directory has visible entries, even if they were not loaded
+    #[serde(rename = "hasChildren")]
+    has_children: bool,
     children: Option<Vec<FileNode>>,
+    // Optional metadata, only filled in when requested through `MetadataFlags`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    size: Option<u64>,
+    // Last modification time in milliseconds since the Unix epoch
+    #[serde(skip_serializing_if = "Option::is_none")]
+    modified: Option<u64>,
+    #[serde(rename = "isSymlink", skip_serializing_if = "Option::is_none")]
+    is_symlink: Option<bool>,
+    #[serde(rename = "isBinary", skip_serializing_if = "Option::is_none")]
+    is_binary: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    language: Option<String>,
+    #[serde(rename = "lineCount", skip_serializing_if = "Option::is_none")]
+    line_count: Option<usize>,
+    // Content digest for files; for directories, a digest of the children's names and
+    // digests (a Merkle tree). Only set in hash mode.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    hash: Option<String>,
+}
+
+#[derive(Deserialize, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+enum HashAlgorithm {
+    Blake3,
+    Sha256,
+}
+
+// Which optional FileNode fields a scan should compute. Everything is off by default
+// because sniffing and line counting have to open every file.
+#[derive(Deserialize, Default, Clone, Copy)]
+#[serde(rename_all = "camelCase", default)]
+struct MetadataFlags {
+    size: bool,
+    modified: bool,
+    symlink: bool,
+    binary: bool,
+    language: bool,
+    line_count: bool,
+}
+
+#[derive(Clone, Copy)]
+struct ScanOptions {
+    max_depth: Option<usize>,
+    metadata: MetadataFlags,
+    follow_symlinks: bool,
+    hash: Option<HashAlgorithm>,
+}
+
+// A directory the scan could not read or deliberately did not descend into
+#[derive(Serialize)]
+struct ScanWarning {
+    path: String,
+    reason: String,
+}
+
+#[derive(Serialize)]
+struct ProjectTree {
+    nodes: Vec<FileNode>,
+    warnings: Vec<ScanWarning>,
+    // Rolled-up digest of the whole project, only set in hash mode
+    #[serde(skip_serializing_if = "Option::is_none")]
+    hash: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DirectoryPage {
+    entries: Vec<FileNode>,
+    total: usize,
+    offset: usize,
+    has_more: bool,
 }
 
 #[derive(Serialize, Default)]
@@ -20,10 +124,24 @@ struct GitStatus {
     has_remote: bool,
     behind: usize,
     ahead: usize,
+    files: Vec<GitFileStatus>,
+}
+
+// One changed path. Each side is one of "new", "modified", "deleted", "renamed",
+// "typechange", "conflicted" or "ignored"; None means unchanged on that side.
+#[derive(Serialize)]
+struct GitFileStatus {
+    path: String,
+    index_status: Option<&'static str>,
+    worktree_status: Option<&'static str>,
+    // Original path when the entry was detected as a rename
+    renamed_from: Option<String>,
 }
 
 // ... [Existing File System Code remains unchanged] ...
-// Helper to check if a directory should be ignored
+// Helper to check if a directory should be ignored.
+// Only used as a fallback when the project is not inside a git repository;
+// repositories get their rules from .gitignore and friends instead.
 fn is_ignored(name: &str) -> bool {
     matches!(
         name,
@@ -39,52 +157,62 @@ fn is_ignored(name: &str) -> bool {
     )
 }
 
-fn build_tree(path: &Path, root_base: &Path) -> Result<Vec<FileNode>, String> {
-    let mut nodes = Vec::new();
-    let entries = fs::read_dir(path).map_err(|e| e.to_string())?;
+// Project-local ignore file, same syntax as .gitignore, honored in and out of git repos
+const MAKER_IGNORE_FILE: &str = ".makerignore";
 
-    for entry in entries {
-        let entry = entry.map_err(|e| e.to_string())?;
-        let metadata = entry.metadata().map_err(|e| e.to_string())?;
-        let file_name = entry.file_name().to_string_lossy().to_string();
-        let path_buf = entry.path();
-        
-        let relative_path = path_buf.strip_prefix(root_base)
-            .map(|p| format!("/{}", p.to_string_lossy().replace("\\", "/")))
-            .unwrap_or_else(|_| format!("/{}", file_name));
-
-        let is_dir = metadata.is_dir();
+// Never walked, whatever the ignore files say: git's own data, and MAKER's state whose
+// `.maker/worktrees` holds full checkouts of the project
+const PRUNED_DIRS: [&str; 2] = [".git", ".maker"];
 
-        let mut node = FileNode {
-            name: file_name.clone(),
-            path: relative_path,
-            is_directory: is_dir,
-            children: None,
-        };
+// Settings shared by every walker started for one scan. Future search commands should
+// go through `tree_walker` with these too, so they see the same files as the tree.
+#[derive(Clone)]
+struct WalkRules {
+    in_repo: bool,
+    follow_links: bool,
+    // Project rules from .maker/config.toml
+    config: Arc<ScanMatcher>,
+}
 
-        if is_dir {
-            if is_ignored(&file_name) {
-                node.children = Some(Vec::new());
-            } else {
-                match build_tree(&path_buf, root_base) {
-                    Ok(children) => {
-                        let mut sorted_children = children;
-                        sorted_children.sort_by(|a, b| {
-                            match (a.is_directory, b.is_directory) {
-                                (true, false) => std::cmp::Ordering::Less,
-                                (false, true) => std::cmp::Ordering::Greater,
-                                _ => a.name.cmp(&b.name),
-                            }
-                        });
-                        node.children = Some(sorted_children);
-                    }
-                    Err(_) => node.children = Some(Vec::new()),
-                }
-            }
+impl WalkRules {
+    fn for_root(root: &Path, follow_links: bool) -> Self {
+        WalkRules {
+            in_repo: Repository::discover(root).is_ok(),
+            follow_links,
+            config: Arc::new(ScanMatcher::for_root(root)),
         }
-        nodes.push(node);
     }
+}
+
+// When following links, the walker compares each directory against its ancestors by
+// device and inode (file index on Windows) and yields a Loop error instead of recursing.
+fn tree_walker(root: &Path, rules: &WalkRules) -> WalkBuilder {
+    let mut builder = WalkBuilder::new(root);
+    builder
+        .hidden(false)
+        .follow_links(rules.follow_links)
+        .git_ignore(true)
+        .git_exclude(true)
+        .git_global(true)
+        .add_custom_ignore_filename(MAKER_IGNORE_FILE);
 
+    let in_repo = rules.in_repo;
+    let config = rules.config.clone();
+    builder.filter_entry(move |entry| {
+        let name = entry.file_name().to_string_lossy();
+        if PRUNED_DIRS.contains(&name.as_ref()) {
+            return false;
+        }
+        let is_dir = entry.file_type().map(|ft| ft.is_dir()).unwrap_or(false);
+        match config.decide(entry.path(), is_dir) {
+            Some(visible) => visible,
+            None => in_repo || !is_dir || !is_ignored(&name),
+        }
+    });
+    builder
+}
+
+fn sort_nodes(nodes: &mut [FileNode]) {
     nodes.sort_by(|a, b| {
         match (a.is_directory, b.is_directory) {
             (true, false) => std::cmp::Ordering::Less,
@@ -92,24 +220,609 @@ fn build_tree(path: &Path, root_base: &Path) -> Result<Vec<FileNode>, String> {
             _ => a.name.cmp(&b.name),
         }
     });
-    Ok(nodes)
 }
 
+// Same heuristic as git: a NUL byte in the first 8000 bytes means binary
+const BINARY_SNIFF_LEN: usize = 8000;
+
+fn language_for_path(path: &Path) -> Option<&'static str> {
+    let file_name = path.file_name()?.to_str()?;
+    match file_name {
+        "Dockerfile" => return Some("Dockerfile"),
+        "Makefile" | "makefile" => return Some("Makefile"),
+        _ => {}
+    }
+    let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+    let language = match ext.as_str() {
+        "rs" => "Rust",
+        "ts" | "mts" | "cts" => "TypeScript",
+        "tsx" => "TSX",
+        "js" | "mjs" | "cjs" => "JavaScript",
+        "jsx" => "JSX",
+        "py" | "pyi" => "Python",
+        "go" => "Go",
+        "java" => "Java",
+        "kt" | "kts" => "Kotlin",
+        "swift" => "Swift",
+        "c" | "h" => "C",
+        "cc" | "cpp" | "cxx" | "hpp" | "hh" | "hxx" => "C++",
+        "cs" => "C#",
+        "rb" => "Ruby",
+        "php" => "PHP",
+        "scala" => "Scala",
+        "dart" => "Dart",
+        "lua" => "Lua",
+        "sh" | "bash" | "zsh" => "Shell",
+        "ps1" => "PowerShell",
+        "html" | "htm" => "HTML",
+        "css" => "CSS",
+        "scss" | "sass" => "SCSS",
+        "vue" => "Vue",
+        "svelte" => "Svelte",
+        "json" => "JSON",
+        "toml" => "TOML",
+        "yaml" | "yml" => "YAML",
+        "xml" => "XML",
+        "md" | "markdown" => "Markdown",
+        "sql" => "SQL",
+        _ => return None,
+    };
+    Some(language)
+}
+
+enum ContentHasher {
+    Blake3(Box<blake3::Hasher>),
+    Sha256(Sha256),
+}
+
+impl ContentHasher {
+    fn new(algorithm: HashAlgorithm) -> Self {
+        match algorithm {
+            HashAlgorithm::Blake3 => ContentHasher::Blake3(Box::new(blake3::Hasher::new())),
+            HashAlgorithm::Sha256 => ContentHasher::Sha256(Sha256::new()),
+        }
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        match self {
+            ContentHasher::Blake3(hasher) => {
+                hasher.update(data);
+            }
+            ContentHasher::Sha256(hasher) => hasher.update(data),
+        }
+    }
+
+    fn finish(self) -> String {
+        match self {
+            ContentHasher::Blake3(hasher) => hasher.finalize().to_hex().to_string(),
+            ContentHasher::Sha256(hasher) => hasher
+                .finalize()
+                .iter()
+                .map(|b| format!("{:02x}", b))
+                .collect(),
+        }
+    }
+}
+
+impl io::Write for ContentHasher {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.update(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+// Hex digest of a file's contents, None if it cannot be read
+fn hash_file(path: &Path, algorithm: HashAlgorithm) -> Option<String> {
+    let mut file = fs::File::open(path).ok()?;
+    let mut hasher = ContentHasher::new(algorithm);
+    io::copy(&mut file, &mut hasher).ok()?;
+    Some(hasher.finish())
+}
+
+// Fills in directory digests bottom-up from the file digests already on the nodes and
+// returns the digest of `nodes` as a whole. A directory whose subtree is not fully
+// loaded or hashed (depth limit, unreadable file) gets no digest, and neither do its ancestors.
+fn roll_up_hashes(nodes: &mut [FileNode], algorithm: HashAlgorithm) -> Option<String> {
+    for node in nodes.iter_mut().filter(|n| n.is_directory) {
+        node.hash = match node.children.as_mut() {
+            Some(children) => roll_up_hashes(children, algorithm),
+            None => None,
+        };
+    }
+
+    let mut hasher = ContentHasher::new(algorithm);
+    for node in nodes.iter() {
+        let hash = node.hash.as_deref()?;
+        hasher.update(if node.is_directory { b"tree " } else { b"blob " });
+        hasher.update(node.name.as_bytes());
+        hasher.update(b"\0");
+        hasher.update(hash.as_bytes());
+        hasher.update(b"\n");
+    }
+    Some(hasher.finish())
+}
+
+// Reads the file once to answer both "is it binary" and "how many lines"
+fn sniff_file(path: &Path, want_binary: bool, want_lines: bool) -> (Option<bool>, Option<usize>) {
+    let mut file = match fs::File::open(path) {
+        Ok(file) => file,
+        Err(_) => return (None, None),
+    };
+    let mut buf = vec![0u8; 64 * 1024];
+    let mut read_total = 0usize;
+    let mut newlines = 0usize;
+    let mut last_byte = None;
+    let mut is_binary = false;
+
+    loop {
+        let n = match file.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(_) => return (None, None),
+        };
+        let chunk = &buf[..n];
+        if read_total < BINARY_SNIFF_LEN {
+            let sniff_end = n.min(BINARY_SNIFF_LEN - read_total);
+            is_binary = is_binary || chunk[..sniff_end].contains(&0);
+        }
+        read_total += n;
+        // Line counts are meaningless for binaries, and a binary answer only needs the head
+        if !want_lines || is_binary {
+            if read_total >= BINARY_SNIFF_LEN || is_binary {
+                break;
+            }
+            continue;
+        }
+        newlines += chunk.iter().filter(|&&b| b == b'\n').count();
+        last_byte = chunk.last().copied();
+    }
+
+    let line_count = if want_lines && !is_binary {
+        // A trailing line without a newline still counts
+        Some(newlines + usize::from(last_byte.is_some_and(|b| b != b'\n')))
+    } else {
+        None
+    };
+    (want_binary.then_some(is_binary), line_count)
+}
+
+fn relative_path(path: &Path, root_base: &Path) -> String {
+    path.strip_prefix(root_base)
+        .map(|p| format!("/{}", p.to_string_lossy().replace("\\", "/")))
+        .unwrap_or_else(|_| path.to_string_lossy().to_string())
+}
+
+fn make_node(entry: &ignore::DirEntry, root_base: &Path, flags: &MetadataFlags) -> FileNode {
+    let path_buf = entry.path();
+    let file_name = entry.file_name().to_string_lossy().to_string();
+    let relative_path = relative_path(path_buf, root_base);
+
+    let is_dir = entry.file_type().map(|ft| ft.is_dir()).unwrap_or(false);
+
+    let mut node = FileNode {
+        name: file_name,
+        path: relative_path,
+        is_directory: is_dir,
+        has_children: false,
+        children: None,
+        size: None,
+        modified: None,
+        is_symlink: None,
+        is_binary: None,
+        language: None,
+        line_count: None,
+        hash: None,
+    };
+
+    if flags.symlink {
+        node.is_symlink = Some(entry.path_is_symlink());
+    }
+    if flags.size || flags.modified {
+        if let Ok(metadata) = entry.metadata() {
+            if flags.size && !is_dir {
+                node.size = Some(metadata.len());
+            }
+            if flags.modified {
+                node.modified = metadata
+                    .modified()
+                    .ok()
+                    .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                    .map(|d| d.as_millis() as u64);
+            }
+        }
+    }
+    if is_dir {
+        return node;
+    }
+    if flags.language {
+        node.language = language_for_path(path_buf).map(str::to_string);
+    }
+    if flags.binary || flags.line_count {
+        let (is_binary, line_count) = sniff_file(path_buf, flags.binary, flags.line_count);
+        node.is_binary = is_binary;
+        node.line_count = line_count;
+    }
+    node
+}
+
+// Peeks one level down so unexpanded folders can still show an expand arrow
+fn dir_has_children(dir: &Path, rules: &WalkRules) -> bool {
+    tree_walker(dir, rules)
+        .max_depth(Some(1))
+        .build()
+        .filter_map(Result::ok)
+        .any(|entry| entry.depth() > 0 && !is_unfollowed_dir_link(&entry, rules))
+}
+
+// Flat result of a walk: directory -> its visible entries, children not yet attached
+type DirListings = HashMap<PathBuf, Vec<FileNode>>;
+
+// Assemble the nested tree from the flat (parent dir -> entries) map produced by the walker.
+// Directories deeper than `max_depth` are left unloaded (`children: None`).
+fn assemble_tree(
+    dir: &Path,
+    entries: &mut DirListings,
+    depth: usize,
+    max_depth: Option<usize>,
+    rules: &WalkRules,
+) -> Vec<FileNode> {
+    let mut nodes = entries.remove(dir).unwrap_or_default();
+    for node in nodes.iter_mut() {
+        if !node.is_directory {
+            continue;
+        }
+        let child_dir = dir.join(&node.name);
+        if max_depth.is_none_or(|max| depth < max) {
+            let children = assemble_tree(&child_dir, entries, depth + 1, max_depth, rules);
+            node.has_children = !children.is_empty();
+            node.children = Some(children);
+        } else {
+            node.has_children = dir_has_children(&child_dir, rules);
+        }
+    }
+    sort_nodes(&mut nodes);
+    nodes
+}
+
+fn scan_warning(err: &ignore::Error, root_base: &Path) -> ScanWarning {
+    match err {
+        ignore::Error::WithDepth { err, .. } => scan_warning(err, root_base),
+        ignore::Error::Loop { ancestor, child } => ScanWarning {
+            path: relative_path(child, root_base),
+            reason: format!("Symlink loop back to {}", relative_path(ancestor, root_base)),
+        },
+        ignore::Error::WithPath { path, err } => ScanWarning {
+            path: relative_path(path, root_base),
+            reason: err.to_string(),
+        },
+        other => ScanWarning {
+            path: "/".to_string(),
+            reason: other.to_string(),
+        },
+    }
+}
+
+// A symlink to a directory that the walk does not follow. Such links are left out (and
+// reported as a warning by scans) instead of showing up as files that can't be read.
+fn is_unfollowed_dir_link(entry: &ignore::DirEntry, rules: &WalkRules) -> bool {
+    !rules.follow_links && entry.path_is_symlink() && entry.path().is_dir()
+}
+
+enum ScanItem {
+    Node(PathBuf, FileNode),
+    Warning(ScanWarning),
+}
+
+// Walks `walk_root` (the project root or one of its subdirectories) in parallel.
+// Node paths are always relative to `root_base`.
+fn walk_entries(
+    walk_root: &Path,
+    root_base: &Path,
+    options: &ScanOptions,
+    rules: &WalkRules,
+    cancelled: &AtomicBool,
+) -> Result<(DirListings, Vec<ScanWarning>), String> {
+    let (tx, rx) = mpsc::channel::<ScanItem>();
+
+    tree_walker(walk_root, rules)
+        .max_depth(options.max_depth)
+        .build_parallel()
+        .run(|| {
+            let tx = tx.clone();
+            Box::new(move |result| {
+                if cancelled.load(Ordering::Relaxed) {
+                    return WalkState::Quit;
+                }
+                let entry = match result {
+                    Ok(entry) => entry,
+                    Err(err) => {
+                        let _ = tx.send(ScanItem::Warning(scan_warning(&err, root_base)));
+                        return WalkState::Continue;
+                    }
+                };
+                if entry.depth() == 0 {
+                    return WalkState::Continue;
+                }
+                if is_unfollowed_dir_link(&entry, rules) {
+                    let _ = tx.send(ScanItem::Warning(ScanWarning {
+                        path: relative_path(entry.path(), root_base),
+                        reason: "Symlinked directory not followed".to_string(),
+                    }));
+                    return WalkState::Continue;
+                }
+                if let Some(parent) = entry.path().parent() {
+                    let mut node = make_node(&entry, root_base, &options.metadata);
+                    if let Some(algorithm) = options.hash.filter(|_| !node.is_directory) {
+                        node.hash = hash_file(entry.path(), algorithm);
+                    }
+                    let _ = tx.send(ScanItem::Node(parent.to_path_buf(), node));
+                }
+                WalkState::Continue
+            })
+        });
+    drop(tx);
+
+    if cancelled.load(Ordering::Relaxed) {
+        return Err("Scan cancelled".to_string());
+    }
+
+    let mut entries = DirListings::new();
+    let mut warnings = Vec::new();
+    for item in rx {
+        match item {
+            ScanItem::Node(parent, node) => entries.entry(parent).or_default().push(node),
+            ScanItem::Warning(warning) => warnings.push(warning),
+        }
+    }
+    warnings.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok((entries, warnings))
+}
+
+fn build_tree(
+    root_base: &Path,
+    options: &ScanOptions,
+    cancelled: &AtomicBool,
+) -> Result<ProjectTree, String> {
+    let rules = WalkRules::for_root(root_base, options.follow_symlinks);
+    let (mut entries, warnings) = walk_entries(root_base, root_base, options, &rules, cancelled)?;
+    let mut nodes = assemble_tree(root_base, &mut entries, 1, options.max_depth, &rules);
+    let hash = options.hash.and_then(|algorithm| roll_up_hashes(&mut nodes, algorithm));
+    Ok(ProjectTree {
+        nodes,
+        warnings,
+        hash,
+    })
+}
+
+// Resolves a project-relative path ("/src/lib") and refuses anything outside the project root
+fn resolve_in_project(root: &Path, relative: &str) -> Result<PathBuf, String> {
+    let root = root.canonicalize().map_err(|e| e.to_string())?;
+    let resolved = root
+        .join(relative.trim_start_matches(['/', '\\']))
+        .canonicalize()
+        .map_err(|e| e.to_string())?;
+    if !resolved.starts_with(&root) {
+        return Err("Path is outside the project".to_string());
+    }
+    Ok(resolved)
+}
+
+// In-flight tree scans, keyed by the scan id the frontend passed in
+#[derive(Default)]
+struct ScanRegistry {
+    scans: Mutex<HashMap<String, Arc<AtomicBool>>>,
+}
+
+impl ScanRegistry {
+    fn register(&self, scan_id: &str) -> Arc<AtomicBool> {
+        let flag = Arc::new(AtomicBool::new(false));
+        let mut scans = self.scans.lock().unwrap();
+        // Re-using an id supersedes the older scan
+        if let Some(previous) = scans.insert(scan_id.to_string(), flag.clone()) {
+            previous.store(true, Ordering::Relaxed);
+        }
+        flag
+    }
+
+    fn finish(&self, scan_id: &str, flag: &Arc<AtomicBool>) {
+        let mut scans = self.scans.lock().unwrap();
+        if scans.get(scan_id).is_some_and(|current| Arc::ptr_eq(current, flag)) {
+            scans.remove(scan_id);
+        }
+    }
+
+    fn cancel(&self, scan_id: &str) -> bool {
+        match self.scans.lock().unwrap().remove(scan_id) {
+            Some(flag) => {
+                flag.store(true, Ordering::Relaxed);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+#[tauri::command]
+async fn get_project_tree(
+    scans: State<'_, ScanRegistry>,
+    path: String,
+    max_depth: Option<usize>,
+    flags: Option<MetadataFlags>,
+    follow_symlinks: Option<bool>,
+    hash: Option<HashAlgorithm>,
+    scan_id: Option<String>,
+) -> Result<ProjectTree, String> {
+    let root_path = PathBuf::from(&path);
+    if !root_path.exists() {
+        return Err("Path does not exist".to_string());
+    }
+
+    let cancelled = match &scan_id {
+        Some(id) => scans.register(id),
+        None => Arc::new(AtomicBool::new(false)),
+    };
+
+    // The walk runs on the blocking pool so other commands are not held up behind it
+    let options = ScanOptions {
+        max_depth,
+        metadata: flags.unwrap_or_default(),
+        follow_symlinks: follow_symlinks.unwrap_or(true),
+        hash,
+    };
+    let flag = cancelled.clone();
+    let result = tauri::async_runtime::spawn_blocking(move || build_tree(&root_path, &options, &flag))
+        .await
+        .map_err(|e| e.to_string())?;
+
+    if let Some(id) = &scan_id {
+        scans.finish(id, &cancelled);
+    }
+    result
+}
+
+#[tauri::command]
+fn cancel_scan(scans: State<'_, ScanRegistry>, scan_id: String) -> bool {
+    scans.cancel(&scan_id)
+}
+
+// Lists a single directory level, sorted like the tree, one page at a time
 #[tauri::command]
-fn get_project_tree(path: String) -> Result<Vec<FileNode>, String> {
+fn list_directory(
+    path: String,
+    dir: Option<String>,
+    offset: Option<usize>,
+    limit: Option<usize>,
+    flags: Option<MetadataFlags>,
+    follow_symlinks: Option<bool>,
+) -> Result<DirectoryPage, String> {
     let root_path = Path::new(&path);
     if !root_path.exists() {
         return Err("Path does not exist".to_string());
     }
-    build_tree(root_path, root_path)
+    let root_base = root_path.canonicalize().map_err(|e| e.to_string())?;
+    let target = resolve_in_project(&root_base, dir.as_deref().unwrap_or("/"))?;
+    if !target.is_dir() {
+        return Err("Not a directory".to_string());
+    }
+    let rules = WalkRules::for_root(&root_base, follow_symlinks.unwrap_or(true));
+    let flags = flags.unwrap_or_default();
+
+    let mut dir_entries: Vec<ignore::DirEntry> = tree_walker(&target, &rules)
+        .max_depth(Some(1))
+        .build()
+        .filter_map(Result::ok)
+        .filter(|entry| entry.depth() > 0 && !is_unfollowed_dir_link(entry, &rules))
+        .collect();
+    // Same order as sort_nodes: directories first, then by name
+    dir_entries.sort_by_cached_key(|entry| {
+        let is_dir = entry.file_type().map(|ft| ft.is_dir()).unwrap_or(false);
+        (!is_dir, entry.file_name().to_string_lossy().to_string())
+    });
+
+    let total = dir_entries.len();
+    let offset = offset.unwrap_or(0).min(total);
+    let limit = limit.unwrap_or(total);
+
+    // Only the returned page pays for metadata and the one-level peek
+    let mut entries: Vec<FileNode> = dir_entries
+        .iter()
+        .skip(offset)
+        .take(limit)
+        .map(|entry| make_node(entry, &root_base, &flags))
+        .collect();
+    for node in entries.iter_mut().filter(|n| n.is_directory) {
+        node.has_children = dir_has_children(&target.join(&node.name), &rules);
+    }
+
+    Ok(DirectoryPage {
+        has_more: offset + entries.len() < total,
+        entries,
+        total,
+        offset,
+    })
 }
 
 // ... [End File System Code] ...
 
 // --- GIT OPERATIONS ---
 
+fn index_state(status: Status) -> Option<&'static str> {
+    if status.is_index_new() {
+        Some("new")
+    } else if status.is_index_modified() {
+        Some("modified")
+    } else if status.is_index_deleted() {
+        Some("deleted")
+    } else if status.is_index_renamed() {
+        Some("renamed")
+    } else if status.is_index_typechange() {
+        Some("typechange")
+    } else {
+        None
+    }
+}
+
+fn worktree_state(status: Status) -> Option<&'static str> {
+    if status.is_wt_new() {
+        Some("new")
+    } else if status.is_wt_modified() {
+        Some("modified")
+    } else if status.is_wt_deleted() {
+        Some("deleted")
+    } else if status.is_wt_renamed() {
+        Some("renamed")
+    } else if status.is_wt_typechange() {
+        Some("typechange")
+    } else if status.is_ignored() {
+        Some("ignored")
+    } else {
+        None
+    }
+}
+
+fn file_status(entry: &StatusEntry) -> GitFileStatus {
+    let status = entry.status();
+    // Renames report the new path as the entry path only through the deltas
+    let head_to_index = entry.head_to_index();
+    let index_to_workdir = entry.index_to_workdir();
+    let rename = [head_to_index, index_to_workdir]
+        .into_iter()
+        .flatten()
+        .find(|delta| delta.status() == Delta::Renamed);
+
+    let path = rename
+        .as_ref()
+        .and_then(|delta| delta.new_file().path())
+        .map(|p| p.to_string_lossy().to_string())
+        .or_else(|| entry.path().map(str::to_string))
+        .unwrap_or_default();
+    let renamed_from = rename
+        .as_ref()
+        .and_then(|delta| delta.old_file().path())
+        .map(|p| p.to_string_lossy().to_string());
+
+    if status.is_conflicted() {
+        return GitFileStatus {
+            path,
+            index_status: Some("conflicted"),
+            worktree_status: Some("conflicted"),
+            renamed_from: None,
+        };
+    }
+    GitFileStatus {
+        path,
+        index_status: index_state(status),
+        worktree_status: worktree_state(status),
+        renamed_from,
+    }
+}
+
 #[tauri::command]
-fn get_git_status(path: String) -> Result<GitStatus, String> {
+fn get_git_status(path: String, include_ignored: Option<bool>) -> Result<GitStatus, String> {
     let repo = match Repository::open(&path) {
         Ok(r) => r,
         Err(_) => return Ok(GitStatus::default()),
@@ -127,9 +840,14 @@ fn get_git_status(path: String) -> Result<GitStatus, String> {
     }
 
     let mut opts = StatusOptions::new();
-    opts.include_untracked(true);
+    opts.include_untracked(true)
+        .recurse_untracked_dirs(true)
+        .include_ignored(include_ignored.unwrap_or(false))
+        .renames_head_to_index(true)
+        .renames_index_to_workdir(true);
     if let Ok(statuses) = repo.statuses(Some(&mut opts)) {
-        status.is_dirty = !statuses.is_empty();
+        status.files = statuses.iter().map(|entry| file_status(&entry)).collect();
+        status.is_dirty = statuses.iter().any(|entry| !entry.status().is_ignored());
     }
 
     if let Ok(head) = repo.head() {
@@ -171,14 +889,99 @@ fn git_add_all(path: String) -> Result<String, String> {
     Ok("Staged all changes".to_string())
 }
 
+const AGENT_NAME: &str = "MakerCode Agent";
+const AGENT_EMAIL: &str = "agent@makercode.dev";
+
+// Explicit author or committer for a commit, overriding git config
+#[derive(Deserialize)]
+pub(crate) struct CommitIdentity {
+    name: String,
+    email: String,
+}
+
+// `user.name` / `user.email` from the repository, global and system config.
+// Falls back to the agent identity when git has not been configured at all.
+fn commit_signature(repo: &Repository) -> Result<Signature<'static>, String> {
+    match repo.signature() {
+        Ok(signature) => Ok(signature),
+        Err(_) => Signature::now(AGENT_NAME, AGENT_EMAIL).map_err(|e| e.message().to_string()),
+    }
+}
+
+// Resolves any revspec (oid, branch, tag, `HEAD~2`, ...) to the commit it points at
+fn resolve_commit<'r>(repo: &'r Repository, spec: &str) -> Result<Commit<'r>, String> {
+    repo.revparse_single(spec)
+        .and_then(|obj| obj.peel_to_commit())
+        .map_err(|e| format!("Cannot resolve '{}': {}", spec, e.message()))
+}
+
+fn identity_signature(identity: &CommitIdentity) -> Result<Signature<'static>, String> {
+    Signature::now(&identity.name, &identity.email)
+        .map_err(|e| format!("Invalid identity '{} <{}>': {}", identity.name, identity.email, e.message()))
+}
+
+// Credits the agent with a `Co-authored-by` trailer, unless it already is the author
+// or the trailer is present
+fn with_agent_trailer(message: &str, author: &Signature) -> String {
+    let trailer = format!("Co-authored-by: {} <{}>", AGENT_NAME, AGENT_EMAIL);
+    if author.email() == Some(AGENT_EMAIL) || message.lines().any(|line| line.trim() == trailer) {
+        return message.to_string();
+    }
+    let message = message.trim_end();
+    // Join an existing trailer block instead of starting a new paragraph
+    let last_paragraph = message.rsplit("\n\n").next().unwrap_or("");
+    let ends_with_trailers = message.contains("\n\n")
+        && last_paragraph.lines().all(|line| {
+            line.split_once(": ")
+                .is_some_and(|(token, _)| !token.is_empty() && !token.contains(' '))
+        });
+    let separator = if ends_with_trailers { "\n" } else { "\n\n" };
+    format!("{}{}{}\n", message, separator, trailer)
+}
+
+// Author, committer and final message of a new commit. Both identities default to the
+// configured git identity; `co_author_agent` (default true) adds the agent trailer.
+fn commit_identities(
+    repo: &Repository,
+    message: String,
+    author: Option<&CommitIdentity>,
+    committer: Option<&CommitIdentity>,
+    co_author_agent: Option<bool>,
+) -> Result<(Signature<'static>, Signature<'static>, String), String> {
+    let default_signature = commit_signature(repo)?;
+    let author = match author {
+        Some(identity) => identity_signature(identity)?,
+        None => default_signature.clone(),
+    };
+    let committer = match committer {
+        Some(identity) => identity_signature(identity)?,
+        None => default_signature,
+    };
+    let message = if co_author_agent.unwrap_or(true) {
+        with_agent_trailer(&message, &author)
+    } else {
+        message
+    };
+    Ok((author, committer, message))
+}
+
+// Commits the index. Author and committer default to the configured git identity;
+// `co_author_agent` (default true) adds the agent as a co-author. `sign` overrides
+// `commit.gpgsign`.
 #[tauri::command]
-fn git_commit(path: String, message: String) -> Result<String, String> {
+fn git_commit(
+    path: String,
+    message: String,
+    author: Option<CommitIdentity>,
+    committer: Option<CommitIdentity>,
+    co_author_agent: Option<bool>,
+    sign: Option<bool>,
+) -> Result<String, String> {
     let repo = Repository::open(&path).map_err(|e| e.message().to_string())?;
     
-    // 1. Prepare the signature
-    // In a real app, we'd read this from git config or user settings
-    let signature = Signature::now("MakerCode Agent", "agent@makercode.dev")
-        .map_err(|e| e.message().to_string())?;
+    // 1. Prepare the signatures
+    let (author, committer, message) =
+        commit_identities(&repo, message, author.as_ref(), committer.as_ref(), co_author_agent)?;
 
     // 2. Get the tree to commit
     let mut index = repo.index().map_err(|e| e.message().to_string())?;
@@ -200,15 +1003,8 @@ fn git_commit(path: String, message: String) -> Result<String, String> {
         vec![]
     };
 
-    // 4. Commit
-    repo.commit(
-        Some("HEAD"),
-        &signature,
-        &signature,
-        &message,
-        &tree,
-        &parents,
-    ).map_err(|e| e.message().to_string())?;
+    // 4. Commit (signed when requested or when commit.gpgsign is set)
+    git_sign::write_commit(&repo, &author, &committer, &message, &tree, &parents, sign)?;
 
     Ok("Commit successful".to_string())
 }
@@ -235,12 +1031,55 @@ pub fn run() {
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_fs::init())
         .plugin(tauri_plugin_dialog::init())
+        .manage(ScanRegistry::default())
+        .manage(TreeWatchers::default())
+        .manage(SnapshotStore::default())
         .invoke_handler(tauri::generate_handler![
             check_system_health,
             get_project_tree,
+            list_directory,
+            cancel_scan,
+            tree_watch::watch_project_tree,
+            tree_watch::unwatch_project_tree,
+            snapshot::snapshot_tree,
+            snapshot::diff_snapshots,
+            snapshot::drop_snapshot,
+            project_config::get_scan_rules,
+            project_config::set_scan_rules,
+            project_identity::detect_project_identity,
             get_git_status,
             git_init,
             git_add_all,
+            git_stage::git_stage,
+            git_stage::git_unstage,
+            git_stage::git_stage_hunks,
+            git_stash::git_stash_save,
+            git_stash::git_stash_list,
+            git_stash::git_stash_apply,
+            git_stash::git_stash_drop,
+            git_worktree::worktree_create,
+            git_worktree::worktree_list,
+            git_worktree::worktree_remove,
+            git_worktree::worktree_prune,
+            git_worktree::maker_gc,
+            git_merge::git_squash_merge,
+            git_merge::git_merge_preview,
+            git_rewrite::git_amend,
+            git_rewrite::git_fixup,
+            git_rewrite::git_squash_range,
+            git_history::git_log,
+            git_diff::git_diff,
+            git_branch::git_branches,
+            git_branch::git_create_branch,
+            git_branch::git_checkout,
+            git_branch::git_delete_branch,
+            git_branch::git_rename_branch,
+            git_remote::git_fetch,
+            git_remote::git_pull,
+            git_remote::git_push,
+            git_auth::git_test_credentials,
+            git_auth::git_store_token,
+            git_auth::git_delete_token,
             git_commit
         ])
         .setup(|app| {
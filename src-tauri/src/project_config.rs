@@ -0,0 +1,118 @@
+// Per-project settings stored in `.maker/config.toml`. Only the `[scan]` table is owned
+// here; any other tables in the file are preserved when it is rewritten.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use ignore::Match;
+use serde::{Deserialize, Serialize};
+
+pub(crate) const CONFIG_FILE: &str = ".maker/config.toml";
+
+// Gitignore-style globs, relative to the project root.
+// `ignore` hides matching paths from the scanner; `include` un-hides paths that the
+// built-in fallback list or an `ignore` pattern would hide. Paths hidden by .gitignore
+// can only be brought back with a `!pattern` line in .makerignore.
+#[derive(Serialize, Deserialize, Default, Clone)]
+#[serde(default)]
+pub(crate) struct ScanRules {
+    pub(crate) ignore: Vec<String>,
+    pub(crate) include: Vec<String>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct ProjectConfig {
+    scan: ScanRules,
+}
+
+fn config_path(root: &Path) -> PathBuf {
+    root.join(CONFIG_FILE)
+}
+
+pub(crate) fn load_scan_rules(root: &Path) -> Result<ScanRules, String> {
+    let path = config_path(root);
+    if !path.exists() {
+        return Ok(ScanRules::default());
+    }
+    let text = fs::read_to_string(&path).map_err(|e| e.to_string())?;
+    let config: ProjectConfig =
+        toml::from_str(&text).map_err(|e| format!("Invalid {}: {}", CONFIG_FILE, e))?;
+    Ok(config.scan)
+}
+
+// Compiled form of ScanRules, evaluated by the walker's entry filter
+pub(crate) struct ScanMatcher {
+    matcher: Gitignore,
+}
+
+impl ScanMatcher {
+    pub(crate) fn build(root: &Path, rules: &ScanRules) -> Result<Self, String> {
+        let mut builder = GitignoreBuilder::new(root);
+        for pattern in &rules.ignore {
+            builder
+                .add_line(None, pattern)
+                .map_err(|e| format!("Invalid ignore pattern '{}': {}", pattern, e))?;
+        }
+        // Later lines win, so includes override ignores just like `!` lines in a .gitignore
+        for pattern in &rules.include {
+            builder
+                .add_line(None, &format!("!{}", pattern))
+                .map_err(|e| format!("Invalid include pattern '{}': {}", pattern, e))?;
+        }
+        let matcher = builder.build().map_err(|e| e.to_string())?;
+        Ok(ScanMatcher { matcher })
+    }
+
+    // Loads the project's rules, falling back to none if the config is missing or broken
+    pub(crate) fn for_root(root: &Path) -> Self {
+        let rules = load_scan_rules(root).unwrap_or_else(|e| {
+            log::warn!("{}", e);
+            ScanRules::default()
+        });
+        ScanMatcher::build(root, &rules).unwrap_or_else(|e| {
+            log::warn!("{}", e);
+            ScanMatcher {
+                matcher: Gitignore::empty(),
+            }
+        })
+    }
+
+    // Some(true) = force-include, Some(false) = hide, None = no opinion
+    pub(crate) fn decide(&self, path: &Path, is_dir: bool) -> Option<bool> {
+        match self.matcher.matched(path, is_dir) {
+            Match::Ignore(_) => Some(false),
+            Match::Whitelist(_) => Some(true),
+            Match::None => None,
+        }
+    }
+}
+
+#[tauri::command]
+pub fn get_scan_rules(path: String) -> Result<ScanRules, String> {
+    load_scan_rules(Path::new(&path))
+}
+
+// Replaces the `[scan]` table of .maker/config.toml and returns the stored rules
+#[tauri::command]
+pub fn set_scan_rules(path: String, rules: ScanRules) -> Result<ScanRules, String> {
+    let root = Path::new(&path);
+    // Reject patterns the walker could not compile before touching the file
+    ScanMatcher::build(root, &rules)?;
+
+    let file = config_path(root);
+    let mut table: toml::Table = match fs::read_to_string(&file) {
+        Ok(text) => toml::from_str(&text).map_err(|e| format!("Invalid {}: {}", CONFIG_FILE, e))?,
+        Err(_) => toml::Table::new(),
+    };
+    let scan = toml::Value::try_from(&rules).map_err(|e| e.to_string())?;
+    table.insert("scan".to_string(), scan);
+
+    if let Some(dir) = file.parent() {
+        fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+    let text = toml::to_string_pretty(&table).map_err(|e| e.to_string())?;
+    fs::write(&file, text).map_err(|e| e.to_string())?;
+    Ok(rules)
+}
@@ -0,0 +1,434 @@
+// Native replacement for the ContextManager's `analyzeProjectIdentity`: language stats by
+// bytes and lines (like linguist) plus what the project manifests say about packaging.
+
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::AtomicBool;
+
+use serde::Serialize;
+
+use super::{walk_entries, DirListings, MetadataFlags, ScanOptions, WalkRules};
+
+// Data and prose formats still show up in the stats, but never win primary language
+const NON_PROGRAMMING: &[&str] = &["JSON", "TOML", "YAML", "XML", "Markdown"];
+
+const MANIFESTS: &[&str] = &["Cargo.toml", "package.json", "pyproject.toml", "go.mod"];
+
+// Lockfile name -> (package manager, ecosystem), in order of preference
+const LOCKFILES: &[(&str, &str, &str)] = &[
+    ("pnpm-lock.yaml", "pnpm", "npm"),
+    ("yarn.lock", "yarn", "npm"),
+    ("bun.lockb", "bun", "npm"),
+    ("bun.lock", "bun", "npm"),
+    ("package-lock.json", "npm", "npm"),
+    ("Cargo.lock", "cargo", "cargo"),
+    ("uv.lock", "uv", "python"),
+    ("poetry.lock", "poetry", "python"),
+    ("Pipfile.lock", "pipenv", "python"),
+    ("go.sum", "go", "go"),
+];
+
+// Dependency name -> framework label
+const FRAMEWORKS: &[(&str, &str)] = &[
+    // JavaScript / TypeScript
+    ("react", "React"),
+    ("next", "Next.js"),
+    ("vue", "Vue"),
+    ("nuxt", "Nuxt"),
+    ("svelte", "Svelte"),
+    ("@sveltejs/kit", "SvelteKit"),
+    ("@angular/core", "Angular"),
+    ("solid-js", "Solid"),
+    ("astro", "Astro"),
+    ("@remix-run/react", "Remix"),
+    ("express", "Express"),
+    ("@nestjs/core", "NestJS"),
+    ("electron", "Electron"),
+    ("@tauri-apps/api", "Tauri"),
+    ("vite", "Vite"),
+    ("tailwindcss", "Tailwind CSS"),
+    ("jest", "Jest"),
+    ("vitest", "Vitest"),
+    // Rust
+    ("tauri", "Tauri"),
+    ("axum", "Axum"),
+    ("actix-web", "Actix Web"),
+    ("rocket", "Rocket"),
+    ("warp", "Warp"),
+    ("tokio", "Tokio"),
+    ("bevy", "Bevy"),
+    ("leptos", "Leptos"),
+    ("yew", "Yew"),
+    // Python
+    ("django", "Django"),
+    ("flask", "Flask"),
+    ("fastapi", "FastAPI"),
+    ("pytest", "pytest"),
+    ("numpy", "NumPy"),
+    ("pandas", "pandas"),
+    ("torch", "PyTorch"),
+    ("tensorflow", "TensorFlow"),
+    // Go
+    ("github.com/gin-gonic/gin", "Gin"),
+    ("github.com/labstack/echo/v4", "Echo"),
+    ("github.com/gofiber/fiber/v2", "Fiber"),
+    ("github.com/spf13/cobra", "Cobra"),
+];
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct LanguageStats {
+    language: String,
+    files: usize,
+    bytes: u64,
+    lines: usize,
+    // Share of all counted bytes, 0-100
+    percentage: f64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ManifestInfo {
+    path: String,
+    kind: String,
+    name: Option<String>,
+    workspace_members: Vec<String>,
+    dependencies: Vec<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ProjectIdentity {
+    primary_language: String,
+    package_manager: String,
+    forbidden_keywords: Vec<String>,
+    frameworks: Vec<String>,
+    languages: Vec<LanguageStats>,
+    manifests: Vec<ManifestInfo>,
+    lockfiles: Vec<String>,
+    workspace_members: Vec<String>,
+}
+
+fn count_languages(listings: &DirListings) -> Vec<LanguageStats> {
+    let mut totals: HashMap<&str, (usize, u64, usize)> = HashMap::new();
+    for node in listings.values().flatten() {
+        if node.is_directory || node.is_binary == Some(true) {
+            continue;
+        }
+        if let Some(language) = node.language.as_deref() {
+            let entry = totals.entry(language).or_default();
+            entry.0 += 1;
+            entry.1 += node.size.unwrap_or(0);
+            entry.2 += node.line_count.unwrap_or(0);
+        }
+    }
+
+    let all_bytes: u64 = totals.values().map(|t| t.1).sum();
+    let mut stats: Vec<LanguageStats> = totals
+        .into_iter()
+        .map(|(language, (files, bytes, lines))| LanguageStats {
+            language: language.to_string(),
+            files,
+            bytes,
+            lines,
+            percentage: if all_bytes == 0 {
+                0.0
+            } else {
+                (bytes as f64 * 1000.0 / all_bytes as f64).round() / 10.0
+            },
+        })
+        .collect();
+    stats.sort_by(|a, b| b.bytes.cmp(&a.bytes).then_with(|| a.language.cmp(&b.language)));
+    stats
+}
+
+fn string_array(value: Option<&toml::Value>) -> Vec<String> {
+    value
+        .and_then(|v| v.as_array())
+        .map(|items| items.iter().filter_map(|i| i.as_str().map(str::to_string)).collect())
+        .unwrap_or_default()
+}
+
+fn table_keys(value: Option<&toml::Value>) -> Vec<String> {
+    value
+        .and_then(|v| v.as_table())
+        .map(|t| t.keys().cloned().collect())
+        .unwrap_or_default()
+}
+
+fn parse_cargo(text: &str, manifest: &mut ManifestInfo) -> Result<(), String> {
+    let doc: toml::Table = toml::from_str(text).map_err(|e| e.to_string())?;
+    let package = doc.get("package");
+    manifest.name = package
+        .and_then(|p| p.get("name"))
+        .and_then(|n| n.as_str())
+        .map(str::to_string);
+
+    let workspace = doc.get("workspace");
+    manifest.workspace_members = string_array(workspace.and_then(|w| w.get("members")));
+    for section in ["dependencies", "dev-dependencies", "build-dependencies"] {
+        manifest.dependencies.extend(table_keys(doc.get(section)));
+    }
+    manifest.dependencies.extend(table_keys(workspace.and_then(|w| w.get("dependencies"))));
+    Ok(())
+}
+
+fn parse_package_json(text: &str, manifest: &mut ManifestInfo) -> Result<Option<String>, String> {
+    let doc: serde_json::Value = serde_json::from_str(text).map_err(|e| e.to_string())?;
+    manifest.name = doc["name"].as_str().map(str::to_string);
+
+    // "workspaces" is either an array of globs or { "packages": [...] } (yarn)
+    let workspaces = doc["workspaces"]
+        .as_array()
+        .or_else(|| doc["workspaces"]["packages"].as_array());
+    manifest.workspace_members = workspaces
+        .map(|items| items.iter().filter_map(|i| i.as_str().map(str::to_string)).collect())
+        .unwrap_or_default();
+
+    for section in ["dependencies", "devDependencies", "peerDependencies"] {
+        if let Some(deps) = doc[section].as_object() {
+            manifest.dependencies.extend(deps.keys().cloned());
+        }
+    }
+
+    // Corepack's "packageManager": "pnpm@9.1.0"
+    Ok(doc["packageManager"]
+        .as_str()
+        .and_then(|pm| pm.split('@').next())
+        .map(str::to_string))
+}
+
+// Name part of a PEP 508 requirement such as "requests[socks]>=2.0; python_version<'3.9'"
+fn pep508_name(requirement: &str) -> &str {
+    let end = requirement
+        .find(|c: char| !(c.is_alphanumeric() || c == '-' || c == '_' || c == '.'))
+        .unwrap_or(requirement.len());
+    requirement[..end].trim()
+}
+
+fn parse_pyproject(text: &str, manifest: &mut ManifestInfo) -> Result<(), String> {
+    let doc: toml::Table = toml::from_str(text).map_err(|e| e.to_string())?;
+    let project = doc.get("project");
+    let poetry = doc.get("tool").and_then(|t| t.get("poetry"));
+
+    manifest.name = project
+        .or(poetry)
+        .and_then(|p| p.get("name"))
+        .and_then(|n| n.as_str())
+        .map(str::to_string);
+
+    for requirement in string_array(project.and_then(|p| p.get("dependencies"))) {
+        manifest.dependencies.push(pep508_name(&requirement).to_lowercase());
+    }
+    manifest.dependencies.extend(
+        table_keys(poetry.and_then(|p| p.get("dependencies")))
+            .into_iter()
+            .filter(|name| name != "python"),
+    );
+
+    let uv_workspace = doc
+        .get("tool")
+        .and_then(|t| t.get("uv"))
+        .and_then(|u| u.get("workspace"));
+    manifest.workspace_members = string_array(uv_workspace.and_then(|w| w.get("members")));
+    Ok(())
+}
+
+fn parse_go_mod(text: &str, manifest: &mut ManifestInfo) {
+    let mut in_require_block = false;
+    for line in text.lines().map(|l| l.split("//").next().unwrap_or("").trim()) {
+        if let Some(module) = line.strip_prefix("module ") {
+            manifest.name = Some(module.trim().to_string());
+        } else if line.starts_with("require (") {
+            in_require_block = true;
+        } else if in_require_block && line == ")" {
+            in_require_block = false;
+        } else if let Some(require) = line.strip_prefix("require ") {
+            if let Some(module) = require.split_whitespace().next() {
+                manifest.dependencies.push(module.to_string());
+            }
+        } else if in_require_block {
+            if let Some(module) = line.split_whitespace().next() {
+                manifest.dependencies.push(module.to_string());
+            }
+        }
+    }
+}
+
+// Returns the manifest plus any package manager it names explicitly
+fn parse_manifest(file: &Path, relative: &str, kind: &str) -> Option<(ManifestInfo, Option<String>)> {
+    let text = fs::read_to_string(file).ok()?;
+    let mut manifest = ManifestInfo {
+        path: relative.to_string(),
+        kind: kind.to_string(),
+        name: None,
+        workspace_members: Vec::new(),
+        dependencies: Vec::new(),
+    };
+    let mut declared_manager = None;
+    let parsed = match kind {
+        "cargo" => parse_cargo(&text, &mut manifest),
+        "npm" => parse_package_json(&text, &mut manifest).map(|pm| declared_manager = pm),
+        "python" => parse_pyproject(&text, &mut manifest),
+        _ => {
+            parse_go_mod(&text, &mut manifest);
+            Ok(())
+        }
+    };
+    if let Err(e) = parsed {
+        log::warn!("Could not parse {}: {}", relative, e);
+        return None;
+    }
+    manifest.dependencies.sort();
+    manifest.dependencies.dedup();
+    Some((manifest, declared_manager))
+}
+
+fn manifest_kind(file_name: &str) -> &'static str {
+    match file_name {
+        "Cargo.toml" => "cargo",
+        "package.json" => "npm",
+        "pyproject.toml" => "python",
+        _ => "go",
+    }
+}
+
+// Keeps the vocabulary the TypeScript side already feeds into prompts
+fn ecosystem_defaults(kind: &str) -> (&'static str, &'static str, &'static [&'static str]) {
+    match kind {
+        "npm" => ("TypeScript/Node", "npm", &["pip", "cargo", "maven", "gradle"]),
+        "python" => ("Python", "pip", &["npm", "yarn", "node_modules", "cargo"]),
+        "cargo" => ("Rust", "cargo", &["npm", "pip", "node_modules"]),
+        "go" => ("Go", "go", &["npm", "pip", "cargo"]),
+        _ => ("Generic", "None", &[]),
+    }
+}
+
+fn ecosystem_for_language(language: &str) -> &'static str {
+    match language {
+        "TypeScript" | "TSX" | "JavaScript" | "JSX" | "Vue" | "Svelte" => "npm",
+        "Python" => "python",
+        "Rust" => "cargo",
+        "Go" => "go",
+        _ => "",
+    }
+}
+
+fn detect(root: &Path) -> Result<ProjectIdentity, String> {
+    let options = ScanOptions {
+        max_depth: None,
+        metadata: MetadataFlags {
+            size: true,
+            binary: true,
+            language: true,
+            line_count: true,
+            ..Default::default()
+        },
+        follow_symlinks: false,
+        hash: None,
+    };
+    let rules = WalkRules::for_root(root, false);
+    let (listings, _) = walk_entries(root, root, &options, &rules, &AtomicBool::new(false))?;
+
+    let languages = count_languages(&listings);
+
+    let root_files: BTreeSet<&str> = listings
+        .get(root)
+        .map(|nodes| nodes.iter().filter(|n| !n.is_directory).map(|n| n.name.as_str()).collect())
+        .unwrap_or_default();
+    let present_locks: Vec<&(&str, &str, &str)> = LOCKFILES
+        .iter()
+        .filter(|(name, _, _)| root_files.contains(name))
+        .collect();
+    let lockfiles: Vec<String> = present_locks.iter().map(|(name, _, _)| name.to_string()).collect();
+
+    // Manifests anywhere in the visible tree, root first
+    let mut manifest_paths: Vec<(PathBuf, String)> = listings
+        .iter()
+        .flat_map(|(dir, nodes)| {
+            nodes
+                .iter()
+                .filter(|n| !n.is_directory && MANIFESTS.contains(&n.name.as_str()))
+                .map(move |n| (dir.join(&n.name), n.path.clone()))
+        })
+        .collect();
+    manifest_paths.sort_by(|a, b| {
+        let depth = |p: &str| p.matches('/').count();
+        depth(&a.1).cmp(&depth(&b.1)).then_with(|| a.1.cmp(&b.1))
+    });
+
+    let mut manifests = Vec::new();
+    let mut declared_manager = None;
+    for (file, relative) in &manifest_paths {
+        let file_name = file.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+        if let Some((manifest, manager)) = parse_manifest(file, relative, manifest_kind(&file_name)) {
+            declared_manager = declared_manager.or(manager);
+            manifests.push(manifest);
+        }
+    }
+
+    let mut frameworks = Vec::new();
+    for manifest in &manifests {
+        for dependency in &manifest.dependencies {
+            if let Some((_, label)) = FRAMEWORKS.iter().find(|(dep, _)| dep == dependency) {
+                if !frameworks.iter().any(|f| f == label) {
+                    frameworks.push(label.to_string());
+                }
+            }
+        }
+    }
+
+    let workspace_members: Vec<String> = manifests
+        .iter()
+        .flat_map(|m| m.workspace_members.iter().cloned())
+        .collect();
+
+    // A root manifest decides the ecosystem; otherwise the byte counts do
+    let root_manifest = manifests.iter().find(|m| m.path.matches('/').count() == 1);
+    let top_language = languages
+        .iter()
+        .find(|l| !NON_PROGRAMMING.contains(&l.language.as_str()));
+    let ecosystem = match (root_manifest, top_language) {
+        (Some(manifest), _) => manifest.kind.as_str(),
+        (None, Some(language)) => ecosystem_for_language(&language.language),
+        (None, None) => "",
+    };
+    let (ecosystem_language, default_manager, forbidden) = ecosystem_defaults(ecosystem);
+
+    let primary_language = match top_language {
+        Some(language) => language.language.clone(),
+        None => ecosystem_language.to_string(),
+    };
+    // Prefer the lockfile of the detected ecosystem, e.g. Cargo.lock next to a stray package-lock.json
+    let lock_manager = present_locks
+        .iter()
+        .find(|(_, _, lock_ecosystem)| *lock_ecosystem == ecosystem)
+        .or(present_locks.first())
+        .map(|(_, manager, _)| manager.to_string());
+    let package_manager = declared_manager
+        .or(lock_manager)
+        .unwrap_or_else(|| default_manager.to_string());
+
+    Ok(ProjectIdentity {
+        primary_language,
+        package_manager,
+        forbidden_keywords: forbidden.iter().map(|s| s.to_string()).collect(),
+        frameworks,
+        languages,
+        manifests,
+        lockfiles,
+        workspace_members,
+    })
+}
+
+#[tauri::command]
+pub async fn detect_project_identity(path: String) -> Result<ProjectIdentity, String> {
+    let root = PathBuf::from(&path);
+    if !root.exists() {
+        return Err("Path does not exist".to_string());
+    }
+    tauri::async_runtime::spawn_blocking(move || detect(&root))
+        .await
+        .map_err(|e| e.to_string())?
+}
@@ -0,0 +1,142 @@
+// In-memory fingerprints of the project tree, so the flight recorder can tell exactly
+// which files a step created, modified or deleted, even outside git.
+
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use serde::Serialize;
+use tauri::State;
+
+use super::{walk_entries, HashAlgorithm, MetadataFlags, ScanOptions, WalkRules};
+
+struct FileFingerprint {
+    size: u64,
+    modified: Option<u64>,
+    hash: Option<String>,
+}
+
+impl FileFingerprint {
+    // Content hashes win when both sides have one; otherwise fall back to size + mtime
+    fn differs_from(&self, other: &FileFingerprint) -> bool {
+        match (&self.hash, &other.hash) {
+            (Some(a), Some(b)) => self.size != other.size || a != b,
+            _ => self.size != other.size || self.modified != other.modified,
+        }
+    }
+}
+
+struct TreeSnapshot {
+    root: PathBuf,
+    // Keyed by project-relative path, files only
+    files: BTreeMap<String, FileFingerprint>,
+}
+
+#[derive(Serialize)]
+pub(crate) struct SnapshotDiff {
+    created: Vec<String>,
+    modified: Vec<String>,
+    deleted: Vec<String>,
+}
+
+#[derive(Default)]
+pub(crate) struct SnapshotStore {
+    snapshots: Mutex<HashMap<String, TreeSnapshot>>,
+    next_id: AtomicU64,
+}
+
+fn take_snapshot(root: &Path, hash_contents: bool) -> Result<TreeSnapshot, String> {
+    let options = ScanOptions {
+        max_depth: None,
+        metadata: MetadataFlags {
+            size: true,
+            modified: true,
+            ..Default::default()
+        },
+        follow_symlinks: true,
+        hash: hash_contents.then_some(HashAlgorithm::Blake3),
+    };
+    let rules = WalkRules::for_root(root, true);
+    let (listings, _) = walk_entries(root, root, &options, &rules, &AtomicBool::new(false))?;
+
+    let mut files = BTreeMap::new();
+    for node in listings.into_values().flatten().filter(|n| !n.is_directory) {
+        files.insert(
+            node.path,
+            FileFingerprint {
+                size: node.size.unwrap_or(0),
+                modified: node.modified,
+                hash: node.hash,
+            },
+        );
+    }
+    Ok(TreeSnapshot {
+        root: root.to_path_buf(),
+        files,
+    })
+}
+
+fn diff(a: &TreeSnapshot, b: &TreeSnapshot) -> SnapshotDiff {
+    let mut result = SnapshotDiff {
+        created: Vec::new(),
+        modified: Vec::new(),
+        deleted: Vec::new(),
+    };
+    for (path, before) in &a.files {
+        match b.files.get(path) {
+            Some(after) if before.differs_from(after) => result.modified.push(path.clone()),
+            Some(_) => {}
+            None => result.deleted.push(path.clone()),
+        }
+    }
+    result.created = b
+        .files
+        .keys()
+        .filter(|path| !a.files.contains_key(*path))
+        .cloned()
+        .collect();
+    result
+}
+
+// Fingerprints every visible file by size and mtime (plus a BLAKE3 digest when
+// `hash_contents` is set) and returns an id for `diff_snapshots`.
+#[tauri::command]
+pub async fn snapshot_tree(
+    store: State<'_, SnapshotStore>,
+    path: String,
+    hash_contents: Option<bool>,
+) -> Result<String, String> {
+    let root = PathBuf::from(&path);
+    if !root.exists() {
+        return Err("Path does not exist".to_string());
+    }
+    let hash_contents = hash_contents.unwrap_or(false);
+    let snapshot = tauri::async_runtime::spawn_blocking(move || take_snapshot(&root, hash_contents))
+        .await
+        .map_err(|e| e.to_string())??;
+
+    let snapshot_id = format!("snap-{}", store.next_id.fetch_add(1, Ordering::Relaxed) + 1);
+    store
+        .snapshots
+        .lock()
+        .unwrap()
+        .insert(snapshot_id.clone(), snapshot);
+    Ok(snapshot_id)
+}
+
+#[tauri::command]
+pub fn diff_snapshots(store: State<'_, SnapshotStore>, a: String, b: String) -> Result<SnapshotDiff, String> {
+    let snapshots = store.snapshots.lock().unwrap();
+    let before = snapshots.get(&a).ok_or_else(|| format!("Unknown snapshot: {}", a))?;
+    let after = snapshots.get(&b).ok_or_else(|| format!("Unknown snapshot: {}", b))?;
+    if before.root != after.root {
+        return Err("Snapshots belong to different projects".to_string());
+    }
+    Ok(diff(before, after))
+}
+
+#[tauri::command]
+pub fn drop_snapshot(store: State<'_, SnapshotStore>, snapshot_id: String) -> bool {
+    store.snapshots.lock().unwrap().remove(&snapshot_id).is_some()
+}
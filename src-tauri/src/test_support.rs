@@ -0,0 +1,100 @@
+// Throwaway repositories for the git command tests.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use git2::{Oid, Repository, RepositoryInitOptions, Signature};
+
+static NEXT_DIR: AtomicUsize = AtomicUsize::new(0);
+
+// A fresh directory under the system temp dir, deleted again on drop
+pub(crate) struct TempDir(PathBuf);
+
+impl TempDir {
+    pub(crate) fn new(label: &str) -> Self {
+        let n = NEXT_DIR.fetch_add(1, Ordering::Relaxed);
+        let dir = std::env::temp_dir().join(format!("makercode-{}-{}-{}", label, std::process::id(), n));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        TempDir(dir.canonicalize().unwrap())
+    }
+
+    pub(crate) fn join(&self, name: &str) -> PathBuf {
+        self.0.join(name)
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+fn set_identity(repo: &Repository) {
+    let mut config = repo.config().unwrap();
+    config.set_str("user.name", "Test User").unwrap();
+    config.set_str("user.email", "test@example.com").unwrap();
+    config.set_bool("commit.gpgsign", false).unwrap();
+}
+
+// A repository on an unborn `main` branch, with a local identity and signing disabled
+pub(crate) fn init_repo(dir: &Path) -> Repository {
+    let mut opts = RepositoryInitOptions::new();
+    opts.initial_head("main");
+    let repo = Repository::init_opts(dir, &opts).unwrap();
+    set_identity(&repo);
+    repo
+}
+
+pub(crate) fn init_bare(dir: &Path) -> Repository {
+    let mut opts = RepositoryInitOptions::new();
+    opts.bare(true).initial_head("main");
+    Repository::init_opts(dir, &opts).unwrap()
+}
+
+pub(crate) fn clone_repo(url: &Path, dir: &Path) -> Repository {
+    let repo = Repository::clone(url.to_str().unwrap(), dir).unwrap();
+    set_identity(&repo);
+    repo
+}
+
+pub(crate) fn path_str(path: &Path) -> String {
+    path.to_string_lossy().to_string()
+}
+
+// Writes `content` to `file` in the working tree and stages it
+pub(crate) fn stage_file(repo: &Repository, file: &str, content: &str) {
+    let full = repo.workdir().unwrap().join(file);
+    if let Some(parent) = full.parent() {
+        fs::create_dir_all(parent).unwrap();
+    }
+    fs::write(full, content).unwrap();
+    let mut index = repo.index().unwrap();
+    index.add_path(Path::new(file)).unwrap();
+    index.write().unwrap();
+}
+
+// Commits whatever is staged on top of HEAD
+pub(crate) fn commit_staged(repo: &Repository, message: &str) -> Oid {
+    let signature = Signature::now("Test User", "test@example.com").unwrap();
+    let tree_id = repo.index().unwrap().write_tree().unwrap();
+    let tree = repo.find_tree(tree_id).unwrap();
+    let parent = repo.head().ok().and_then(|head| head.peel_to_commit().ok());
+    let parents: Vec<_> = parent.iter().collect();
+    repo.commit(Some("HEAD"), &signature, &signature, message, &tree, &parents)
+        .unwrap()
+}
+
+// Contents of `file` in the tree of the commit `spec` resolves to
+pub(crate) fn file_at(repo: &Repository, spec: &str, file: &str) -> Option<String> {
+    let tree = repo.revparse_single(spec).ok()?.peel_to_tree().ok()?;
+    let entry = tree.get_path(Path::new(file)).ok()?;
+    let blob = repo.find_blob(entry.id()).ok()?;
+    Some(String::from_utf8_lossy(blob.content()).to_string())
+}
+
+pub(crate) fn commit_file(repo: &Repository, file: &str, content: &str, message: &str) -> Oid {
+    stage_file(repo, file, content);
+    commit_staged(repo, message)
+}
@@ -0,0 +1,345 @@
+// Rust-side tree cache per project root. After the first scan, file-system notifications
+// are turned into `tree://patch` events so the webview never has to rescan the whole disk.
+
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::AtomicBool;
+use std::sync::{mpsc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use notify::event::{ModifyKind, RenameMode};
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, State};
+
+use super::project_config::CONFIG_FILE;
+use super::{
+    assemble_tree, make_node, relative_path, sort_nodes, tree_walker, walk_entries, DirListings,
+    FileNode, MetadataFlags, ProjectTree, ScanOptions, WalkRules, MAKER_IGNORE_FILE, PRUNED_DIRS,
+};
+
+const TREE_PATCH_EVENT: &str = "tree://patch";
+
+// Events arriving within this window are folded into a single patch
+const DEBOUNCE: Duration = Duration::from_millis(150);
+// A steady stream of events (a build writing to `target/`, an install filling `node_modules/`)
+// would otherwise keep extending the window; a batch is flushed after this long regardless
+const MAX_BATCH_DELAY: Duration = Duration::from_secs(1);
+
+#[derive(Serialize, Clone)]
+struct TreeRename {
+    from: FileNode,
+    to: FileNode,
+}
+
+#[derive(Serialize, Clone, Default)]
+struct TreePatch {
+    root: String,
+    // Added directories carry their whole (visible) subtree in `children`
+    added: Vec<FileNode>,
+    removed: Vec<FileNode>,
+    renamed: Vec<TreeRename>,
+}
+
+impl TreePatch {
+    fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.renamed.is_empty()
+    }
+
+    // A remove + add pair reported by the OS as one rename becomes a `renamed` entry
+    fn pair_renames(&mut self, renames: &[(String, String)]) {
+        for (from, to) in renames {
+            let removed = self.removed.iter().position(|n| &n.path == from);
+            let added = self.added.iter().position(|n| &n.path == to);
+            if let (Some(r), Some(a)) = (removed, added) {
+                let from = self.removed.remove(r);
+                let to = self.added.remove(a);
+                self.renamed.push(TreeRename { from, to });
+            }
+        }
+    }
+}
+
+// The walker only creates listings for directories with at least one visible child. Every
+// directory needs one, or files created in it later are never noticed.
+fn seed_empty_listings(listings: &mut DirListings) {
+    let dirs: Vec<PathBuf> = listings
+        .iter()
+        .flat_map(|(parent, nodes)| {
+            nodes
+                .iter()
+                .filter(|node| node.is_directory)
+                .map(move |node| parent.join(&node.name))
+        })
+        .collect();
+    for dir in dirs {
+        listings.entry(dir).or_default();
+    }
+}
+
+// Flat listing of every visible directory under the root, kept in sync with the disk
+struct TreeCache {
+    root: PathBuf,
+    rules: WalkRules,
+    dirs: DirListings,
+}
+
+impl TreeCache {
+    fn scan_options() -> ScanOptions {
+        ScanOptions {
+            max_depth: None,
+            metadata: MetadataFlags::default(),
+            follow_symlinks: true,
+            hash: None,
+        }
+    }
+
+    fn new(root: PathBuf) -> Result<(Self, ProjectTree), String> {
+        let rules = WalkRules::for_root(&root, true);
+        let (mut dirs, warnings) =
+            walk_entries(&root, &root, &Self::scan_options(), &rules, &AtomicBool::new(false))?;
+        dirs.entry(root.clone()).or_default();
+        seed_empty_listings(&mut dirs);
+
+        let mut listings = dirs.clone();
+        let tree = ProjectTree {
+            nodes: assemble_tree(&root, &mut listings, 1, None, &rules),
+            warnings,
+            hash: None,
+        };
+        Ok((TreeCache { root, rules, dirs }, tree))
+    }
+
+    fn relative(&self, path: &Path) -> String {
+        relative_path(path, &self.root)
+    }
+
+    // Closest directory at or above `path` that the cache has a listing for
+    fn nearest_cached_dir(&self, path: &Path) -> Option<PathBuf> {
+        path.ancestors()
+            .take_while(|p| p.starts_with(&self.root))
+            .find(|p| *p == self.root || self.dirs.contains_key(*p))
+            .map(Path::to_path_buf)
+    }
+
+    fn list_one_level(&self, dir: &Path) -> Vec<FileNode> {
+        if !dir.is_dir() {
+            return Vec::new();
+        }
+        let flags = MetadataFlags::default();
+        tree_walker(dir, &self.rules)
+            .max_depth(Some(1))
+            .build()
+            .filter_map(Result::ok)
+            .filter(|entry| entry.depth() > 0)
+            .map(|entry| make_node(&entry, &self.root, &flags))
+            .collect()
+    }
+
+    fn forget_subtree(&mut self, dir: &Path) {
+        self.dirs.retain(|key, _| !key.starts_with(dir));
+    }
+
+    // Re-lists `dir` and records what changed. Returns the directories that existed
+    // before and still exist, for callers that want to recurse.
+    fn refresh_dir(&mut self, dir: &Path, patch: &mut TreePatch) -> Vec<PathBuf> {
+        let mut fresh = self.list_one_level(dir);
+        let old = self.dirs.remove(dir).unwrap_or_default();
+
+        let same_entry = |a: &FileNode, b: &FileNode| a.name == b.name && a.is_directory == b.is_directory;
+
+        for node in old.iter().filter(|o| !fresh.iter().any(|f| same_entry(o, f))) {
+            if node.is_directory {
+                self.forget_subtree(&dir.join(&node.name));
+            }
+            patch.removed.push(node.clone());
+        }
+
+        let mut kept_dirs = Vec::new();
+        for node in fresh.iter_mut() {
+            let child_dir = dir.join(&node.name);
+            if old.iter().any(|o| same_entry(o, node)) {
+                if node.is_directory {
+                    node.has_children = self.dirs.get(&child_dir).is_some_and(|c| !c.is_empty());
+                    kept_dirs.push(child_dir);
+                }
+                continue;
+            }
+
+            let mut added = node.clone();
+            if node.is_directory {
+                let subtree = walk_entries(
+                    &child_dir,
+                    &self.root,
+                    &Self::scan_options(),
+                    &self.rules,
+                    &AtomicBool::new(false),
+                );
+                let mut listings = subtree.map(|(listings, _)| listings).unwrap_or_default();
+                listings.entry(child_dir.clone()).or_default();
+                seed_empty_listings(&mut listings);
+                self.dirs.extend(listings.clone());
+
+                let children = assemble_tree(&child_dir, &mut listings, 1, None, &self.rules);
+                node.has_children = !children.is_empty();
+                added.has_children = node.has_children;
+                added.children = Some(children);
+            }
+            patch.added.push(added);
+        }
+
+        sort_nodes(&mut fresh);
+        self.dirs.insert(dir.to_path_buf(), fresh);
+        kept_dirs
+    }
+
+    fn refresh_recursive(&mut self, dir: &Path, patch: &mut TreePatch) {
+        for child in self.refresh_dir(dir, patch) {
+            self.refresh_recursive(&child, patch);
+        }
+    }
+
+    fn apply(&mut self, events: Vec<Event>) -> TreePatch {
+        let mut patch = TreePatch {
+            root: self.root.to_string_lossy().to_string(),
+            ..Default::default()
+        };
+        let mut shallow: BTreeSet<PathBuf> = BTreeSet::new();
+        let mut deep: BTreeSet<PathBuf> = BTreeSet::new();
+        let mut renames = Vec::new();
+
+        for event in &events {
+            if matches!(event.kind, EventKind::Access(_)) {
+                continue;
+            }
+            if let EventKind::Modify(ModifyKind::Name(RenameMode::Both)) = event.kind {
+                if let [from, to] = event.paths.as_slice() {
+                    renames.push((self.relative(from), self.relative(to)));
+                }
+            }
+            for path in &event.paths {
+                // Edited scan rules apply to the whole project
+                if *path == self.root.join(CONFIG_FILE) {
+                    self.rules = WalkRules::for_root(&self.root, true);
+                    deep.insert(self.root.clone());
+                    continue;
+                }
+                let relative = path.strip_prefix(&self.root).unwrap_or(path);
+                if relative
+                    .components()
+                    .any(|c| PRUNED_DIRS.iter().any(|dir| c.as_os_str() == *dir))
+                {
+                    continue;
+                }
+                let Some(parent) = path.parent() else { continue };
+                // New ignore rules can hide or reveal anything below them
+                let name = path.file_name().map(|n| n.to_string_lossy());
+                if matches!(name.as_deref(), Some(".gitignore") | Some(MAKER_IGNORE_FILE)) {
+                    deep.insert(parent.to_path_buf());
+                } else {
+                    shallow.insert(parent.to_path_buf());
+                }
+            }
+        }
+
+        // Parents first, so a directory removed by an earlier refresh is not listed again
+        let mut work: Vec<(PathBuf, bool)> = deep.iter().map(|d| (d.clone(), true)).collect();
+        work.extend(shallow.into_iter().filter(|d| !deep.contains(d)).map(|d| (d, false)));
+        work.sort_by_key(|(d, _)| d.components().count());
+
+        let mut refreshed = BTreeSet::new();
+        for (dir, recursive) in work {
+            let Some(target) = self.nearest_cached_dir(&dir) else { continue };
+            let recursive = recursive && target == dir;
+            if !recursive && !refreshed.insert(target.clone()) {
+                continue;
+            }
+            if recursive {
+                self.refresh_recursive(&target, &mut patch);
+            } else {
+                self.refresh_dir(&target, &mut patch);
+            }
+        }
+
+        patch.pair_renames(&renames);
+        patch
+    }
+}
+
+struct ProjectWatch {
+    // Dropping the watcher closes the event channel, which stops the worker thread
+    _watcher: RecommendedWatcher,
+}
+
+// Active watches keyed by canonical project root
+#[derive(Default)]
+pub(crate) struct TreeWatchers {
+    watches: Mutex<HashMap<PathBuf, ProjectWatch>>,
+}
+
+fn run_worker(app: AppHandle, mut cache: TreeCache, rx: mpsc::Receiver<notify::Result<Event>>) {
+    while let Ok(first) = rx.recv() {
+        let opened = Instant::now();
+        let mut batch = vec![first];
+        loop {
+            let remaining = MAX_BATCH_DELAY.saturating_sub(opened.elapsed());
+            if remaining.is_zero() {
+                break;
+            }
+            match rx.recv_timeout(DEBOUNCE.min(remaining)) {
+                Ok(next) => batch.push(next),
+                Err(_) => break,
+            }
+        }
+        let events: Vec<Event> = batch.into_iter().filter_map(Result::ok).collect();
+        let patch = cache.apply(events);
+        if !patch.is_empty() {
+            if let Err(e) = app.emit(TREE_PATCH_EVENT, patch) {
+                log::warn!("Failed to emit tree patch: {}", e);
+            }
+        }
+    }
+}
+
+// Scans the project once, returns the tree and keeps it up to date through `tree://patch` events
+#[tauri::command]
+pub async fn watch_project_tree(
+    app: AppHandle,
+    watchers: State<'_, TreeWatchers>,
+    path: String,
+) -> Result<ProjectTree, String> {
+    let root = Path::new(&path).canonicalize().map_err(|e| e.to_string())?;
+
+    // Watch before scanning: changes made while the scan runs wait in the channel and are
+    // reconciled against the finished cache, instead of being lost
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(move |event| {
+        let _ = tx.send(event);
+    })
+    .map_err(|e| e.to_string())?;
+    watcher
+        .watch(&root, RecursiveMode::Recursive)
+        .map_err(|e| e.to_string())?;
+
+    let scan_root = root.clone();
+    let (cache, tree) = tauri::async_runtime::spawn_blocking(move || TreeCache::new(scan_root))
+        .await
+        .map_err(|e| e.to_string())??;
+
+    thread::spawn(move || run_worker(app, cache, rx));
+
+    // Re-watching a root replaces the previous watch and its cache
+    watchers
+        .watches
+        .lock()
+        .unwrap()
+        .insert(root, ProjectWatch { _watcher: watcher });
+    Ok(tree)
+}
+
+#[tauri::command]
+pub fn unwatch_project_tree(watchers: State<'_, TreeWatchers>, path: String) -> Result<bool, String> {
+    let root = Path::new(&path).canonicalize().map_err(|e| e.to_string())?;
+    Ok(watchers.watches.lock().unwrap().remove(&root).is_some())
+}
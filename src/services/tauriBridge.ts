@@ -44,7 +44,11 @@ export class MockTauriService {
   static async getProjectTree(path: string): Promise<any[]> {
     if (this.isTauri()) {
       try {
-        return await invoke('get_project_tree', { path });
+        const tree: any = await invoke('get_project_tree', { path });
+        if (tree.warnings?.length) {
+          console.warn("Rust tree scan skipped some directories:", tree.warnings);
+        }
+        return tree.nodes;
       } catch (e) {
         console.error("Rust tree scan failed:", e);
         return [];